@@ -3,7 +3,7 @@ use std::f32::consts::PI;
 use bevy::prelude::*;
 
 use crate::inventory::Inventory;
-use crate::player::camera::{FlyCam, GameState, Player, EYE_HEIGHT};
+use crate::player::camera::{FlyCam, GameState, Player};
 use crate::world::block::BlockType;
 
 // --- Colors ---
@@ -20,6 +20,14 @@ const ARM_SWING_ANGLE: f32 = 0.6;
 const LEG_SWING_ANGLE: f32 = 0.5;
 const LOWER_BEND_ANGLE: f32 = 0.3;
 const SWING_LERP_SPEED: f32 = 8.0;
+const SPRINT_SWING_SPEED: f32 = 15.0;
+const SPRINT_SWING_AMPLITUDE: f32 = 1.4;
+const SNEAK_SWING_SPEED: f32 = 5.0;
+/// How far the thighs swing forward when crouching; the shins bend back twice as
+/// far so the feet stay under the hips.
+const CROUCH_THIGH_ANGLE: f32 = 0.6;
+const CROUCH_LEAN_ANGLE: f32 = 0.3;
+const LEG_LENGTH: f32 = 0.70;
 
 // --- Third person ---
 
@@ -67,6 +75,8 @@ pub struct AvatarAnimation {
     pub walk_phase: f32,
     pub swing_amplitude: f32,
     pub last_position: Vec3,
+    /// From 0 standing to 1 fully crouched while sneaking.
+    pub crouch: f32,
 }
 
 #[derive(Resource, Default, PartialEq, Eq, Clone, Copy)]
@@ -173,6 +183,7 @@ fn spawn_avatar(
                 walk_phase: 0.0,
                 swing_amplitude: 0.0,
                 last_position: player.position,
+                crouch: 0.0,
             },
             Transform::from_translation(player.position),
             GlobalTransform::default(),
@@ -362,18 +373,24 @@ fn spawn_avatar(
 
 // --- Sync systems ---
 
+/// How far the avatar sinks at `crouch` (0 to 1) with its knees bent and its feet
+/// still on the ground.
+fn crouch_drop(crouch: f32) -> f32 {
+    LEG_LENGTH * (1.0 - (crouch * CROUCH_THIGH_ANGLE).cos())
+}
+
 fn sync_avatar_position(
     player_query: Query<(&Player, &Transform), With<FlyCam>>,
-    mut avatar_query: Query<&mut Transform, (With<PlayerAvatar>, Without<FlyCam>)>,
+    mut avatar_query: Query<(&mut Transform, &AvatarAnimation), Without<FlyCam>>,
 ) {
     let Ok((player, cam_transform)) = player_query.get_single() else {
         return;
     };
-    let Ok(mut avatar_transform) = avatar_query.get_single_mut() else {
+    let Ok((mut avatar_transform, anim)) = avatar_query.get_single_mut() else {
         return;
     };
 
-    avatar_transform.translation = player.position;
+    avatar_transform.translation = player.position - Vec3::Y * crouch_drop(anim.crouch);
 
     let (yaw, _pitch, _roll) = cam_transform.rotation.to_euler(EulerRot::YXZ);
     avatar_transform.rotation = Quat::from_rotation_y(yaw);
@@ -407,12 +424,26 @@ fn animate_avatar_walk(
     let is_walking = horizontal_delta.length() > 0.001;
     anim.last_position = player.position;
 
-    // Lerp swing amplitude
-    let target_amplitude = if is_walking { 1.0 } else { 0.0 };
+    // Lerp swing amplitude, wider strides when sprinting
+    let target_amplitude = match (is_walking, player.sprinting) {
+        (false, _) => 0.0,
+        (true, false) => 1.0,
+        (true, true) => SPRINT_SWING_AMPLITUDE,
+    };
     anim.swing_amplitude += (target_amplitude - anim.swing_amplitude) * SWING_LERP_SPEED * dt;
 
+    let target_crouch = if player.sneaking { 1.0 } else { 0.0 };
+    anim.crouch += (target_crouch - anim.crouch) * SWING_LERP_SPEED * dt;
+
     if is_walking {
-        anim.walk_phase += WALK_SWING_SPEED * dt;
+        let swing_speed = if player.sprinting {
+            SPRINT_SWING_SPEED
+        } else if player.sneaking {
+            SNEAK_SWING_SPEED
+        } else {
+            WALK_SWING_SPEED
+        };
+        anim.walk_phase += swing_speed * dt;
     }
 
     let phase = anim.walk_phase;
@@ -430,11 +461,18 @@ fn animate_avatar_walk(
     let left_lower_leg_bend = (left_leg_angle.max(0.0) / LEG_SWING_ANGLE.max(0.001)) * LOWER_BEND_ANGLE * amp;
     let right_lower_leg_bend = (right_leg_angle.max(0.0) / LEG_SWING_ANGLE.max(0.001)) * LOWER_BEND_ANGLE * amp;
 
+    // Crouching bends the knees and leans the body forward
+    let crouch_thigh = anim.crouch * CROUCH_THIGH_ANGLE;
+    let crouch_lean = -anim.crouch * CROUCH_LEAN_ANGLE;
+
     for (part, mut transform) in &mut parts_query {
         match part {
             BodyPart::Head => {
                 transform.rotation = Quat::from_rotation_x(pitch);
             }
+            BodyPart::Torso => {
+                transform.rotation = Quat::from_rotation_x(crouch_lean);
+            }
             BodyPart::LeftUpperArm => {
                 transform.rotation = Quat::from_rotation_x(left_arm_angle);
             }
@@ -442,16 +480,18 @@ fn animate_avatar_walk(
                 transform.rotation = Quat::from_rotation_x(right_arm_angle);
             }
             BodyPart::LeftUpperLeg => {
-                transform.rotation = Quat::from_rotation_x(left_leg_angle);
+                transform.rotation = Quat::from_rotation_x(left_leg_angle + crouch_thigh);
             }
             BodyPart::RightUpperLeg => {
-                transform.rotation = Quat::from_rotation_x(right_leg_angle);
+                transform.rotation = Quat::from_rotation_x(right_leg_angle + crouch_thigh);
             }
             BodyPart::LeftLowerLeg => {
-                transform.rotation = Quat::from_rotation_x(left_lower_leg_bend);
+                transform.rotation =
+                    Quat::from_rotation_x(left_lower_leg_bend - 2.0 * crouch_thigh);
             }
             BodyPart::RightLowerLeg => {
-                transform.rotation = Quat::from_rotation_x(right_lower_leg_bend);
+                transform.rotation =
+                    Quat::from_rotation_x(right_lower_leg_bend - 2.0 * crouch_thigh);
             }
             _ => {}
         }
@@ -517,7 +557,7 @@ fn update_held_block(
         &mut Visibility,
     )>,
 ) {
-    let active = inventory.active_block();
+    let active = inventory.active_item();
 
     for (mut display, mat_handle, mut vis) in &mut query {
         if display.current_block == active {
@@ -584,7 +624,7 @@ fn adjust_camera_for_mode(
             // Place eyes at the front surface of the head (offset forward by half head depth)
             let forward = Vec3::new(-yaw.sin(), 0.0, -yaw.cos());
             cam_transform.translation =
-                player.position + Vec3::Y * EYE_HEIGHT + forward * 0.25;
+                player.position + Vec3::Y * player.eye_height() + forward * 0.25;
         }
         CameraMode::ThirdPerson => {
             // Spherical offset: camera orbits behind the player
@@ -594,7 +634,7 @@ fn adjust_camera_for_mode(
                 yaw.cos() * pitch.cos(),
             ) * THIRD_PERSON_DISTANCE;
 
-            let eye_center = player.position + Vec3::Y * EYE_HEIGHT;
+            let eye_center = player.position + Vec3::Y * player.eye_height();
             cam_transform.translation = eye_center + offset;
         }
     }
@@ -3,7 +3,11 @@ use bevy::prelude::*;
 use crate::events::{ItemDroppedToWorldEvent, ItemsCollectedEvent};
 use crate::inventory::{Inventory, ItemStack};
 use crate::player::camera::{FlyCam, Player};
+use crate::render::BlockTextures;
+use crate::render::atlas::BlockAtlas;
+use crate::render::mesh::build_block_mesh;
 use crate::world::chunk::ChunkMap;
+use crate::world::model::solid_box_at;
 
 const PICKUP_RADIUS: f32 = 2.0;
 const PICKUP_DELAY: f32 = 1.5;
@@ -12,6 +16,10 @@ const COLLECT_ABSORB_DIST: f32 = 0.3;
 const DROPPED_ITEM_SCALE: f32 = 0.3;
 const GRAVITY: f32 = 32.0;
 const TERMINAL_VELOCITY: f32 = 50.0;
+/// Upward push of a fluid, enough to float items to the surface, and how quickly
+/// it slows them down.
+const FLUID_BUOYANCY: f32 = 40.0;
+const FLUID_DRAG: f32 = 3.0;
 const ROTATION_SPEED: f32 = 1.5;
 
 #[derive(Component)]
@@ -19,6 +27,8 @@ pub struct DroppedItem {
     pub stack: ItemStack,
     pub velocity: Vec3,
     pub grounded: bool,
+    /// Whether the item is floating in water (or another fluid).
+    pub in_fluid: bool,
     pub age: f32,
     pub collecting: bool,
 }
@@ -42,8 +52,9 @@ impl Plugin for DroppedItemPlugin {
 
 fn spawn_dropped_items(
     mut commands: Commands,
+    atlas: Res<BlockAtlas>,
+    textures: Res<BlockTextures>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     mut ev_drop: EventReader<ItemDroppedToWorldEvent>,
 ) {
     for event in ev_drop.read() {
@@ -54,15 +65,12 @@ fn spawn_dropped_items(
             _ => 3,
         };
 
-        let mesh = meshes.add(Cuboid::new(
-            DROPPED_ITEM_SCALE,
-            DROPPED_ITEM_SCALE,
+        let mesh = meshes.add(build_block_mesh(
+            event.block_type,
+            &atlas,
             DROPPED_ITEM_SCALE,
         ));
-        let material = materials.add(StandardMaterial {
-            base_color: event.block_type.color(),
-            ..default()
-        });
+        let material = textures.item_material_for(event.block_type);
 
         // Spawn parent entity with DroppedItem component
         let mut parent = commands.spawn((
@@ -70,6 +78,7 @@ fn spawn_dropped_items(
                 stack: ItemStack::new(event.block_type, event.count),
                 velocity: event.velocity,
                 grounded: false,
+                in_fluid: false,
                 age: 0.0,
                 collecting: false,
             },
@@ -110,6 +119,13 @@ fn dropped_item_physics(
             continue;
         }
 
+        let pos = transform.translation.floor().as_ivec3();
+        item.in_fluid = chunk_map.get_block(pos.x, pos.y, pos.z).is_fluid();
+        // Water poured over a resting item lifts it again
+        if item.in_fluid {
+            item.grounded = false;
+        }
+
         if item.grounded {
             // Apply friction then stop
             item.velocity.x *= (1.0 - 5.0 * dt).max(0.0);
@@ -120,24 +136,17 @@ fn dropped_item_physics(
             }
 
             // Slide horizontally only
-            let new_x = transform.translation.x + item.velocity.x * dt;
-            let by = (transform.translation.y - 0.01).floor() as i32;
-            let bz = transform.translation.z.floor() as i32;
-            if !chunk_map
-                .get_block(new_x.floor() as i32, by + 1, bz)
-                .is_solid()
-            {
+            let pos = transform.translation;
+            let new_x = pos.x + item.velocity.x * dt;
+            if solid_box_at(Vec3::new(new_x, pos.y, pos.z), &chunk_map).is_none() {
                 transform.translation.x = new_x;
             } else {
                 item.velocity.x = 0.0;
             }
 
-            let new_z = transform.translation.z + item.velocity.z * dt;
-            let bx = transform.translation.x.floor() as i32;
-            if !chunk_map
-                .get_block(bx, by + 1, new_z.floor() as i32)
-                .is_solid()
-            {
+            let pos = transform.translation;
+            let new_z = pos.z + item.velocity.z * dt;
+            if solid_box_at(Vec3::new(pos.x, pos.y, new_z), &chunk_map).is_none() {
                 transform.translation.z = new_z;
             } else {
                 item.velocity.z = 0.0;
@@ -145,37 +154,39 @@ fn dropped_item_physics(
             continue;
         }
 
-        // Airborne: apply gravity
+        // Airborne: apply gravity, or float and slow down in a fluid
         item.velocity.y -= GRAVITY * dt;
+        if item.in_fluid {
+            item.velocity.y += FLUID_BUOYANCY * dt;
+            item.velocity *= (1.0 - FLUID_DRAG * dt).max(0.0);
+        }
         item.velocity.y = item.velocity.y.max(-TERMINAL_VELOCITY);
 
         // Move X
-        let new_x = transform.translation.x + item.velocity.x * dt;
-        let by = transform.translation.y.floor() as i32;
-        let bz = transform.translation.z.floor() as i32;
-        if chunk_map.get_block(new_x.floor() as i32, by, bz).is_solid() {
+        let pos = transform.translation;
+        let new_x = pos.x + item.velocity.x * dt;
+        if solid_box_at(Vec3::new(new_x, pos.y, pos.z), &chunk_map).is_some() {
             item.velocity.x = 0.0;
         } else {
             transform.translation.x = new_x;
         }
 
         // Move Z
-        let new_z = transform.translation.z + item.velocity.z * dt;
-        let bx = transform.translation.x.floor() as i32;
-        if chunk_map.get_block(bx, by, new_z.floor() as i32).is_solid() {
+        let pos = transform.translation;
+        let new_z = pos.z + item.velocity.z * dt;
+        if solid_box_at(Vec3::new(pos.x, pos.y, new_z), &chunk_map).is_some() {
             item.velocity.z = 0.0;
         } else {
             transform.translation.z = new_z;
         }
 
-        // Move Y
-        let new_y = transform.translation.y + item.velocity.y * dt;
-        let check_y = (new_y - 0.01).floor() as i32;
-        let bx = transform.translation.x.floor() as i32;
-        let bz = transform.translation.z.floor() as i32;
+        // Move Y, landing on top of whatever box it falls into
+        let pos = transform.translation;
+        let new_y = pos.y + item.velocity.y * dt;
+        let floor = solid_box_at(Vec3::new(pos.x, new_y - 0.01, pos.z), &chunk_map);
 
-        if item.velocity.y <= 0.0 && chunk_map.get_block(bx, check_y, bz).is_solid() {
-            transform.translation.y = (check_y + 1) as f32 + DROPPED_ITEM_SCALE / 2.0 + 0.02;
+        if let Some(floor) = floor.filter(|_| item.velocity.y <= 0.0) {
+            transform.translation.y = floor.max.y + DROPPED_ITEM_SCALE / 2.0 + 0.02;
             item.velocity.y = 0.0;
             item.grounded = true;
         } else {
@@ -0,0 +1,159 @@
+use bevy::image::ImageSampler;
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use image::{Rgba, RgbaImage};
+
+use crate::events::BlockDamagedEvent;
+
+/// Number of crack images a block goes through while it is mined.
+pub const CRACK_STAGES: usize = 8;
+const CRACK_SIZE: u32 = 16;
+/// Crack lines grown from the middle of the face, each a random walk.
+const CRACK_LINES: u32 = 6;
+/// The overlay sits just outside the block so it isn't hidden by the block's faces.
+const OVERLAY_SIZE: f32 = 1.004;
+
+/// A cube drawn over the block being mined, textured with its crack stage.
+#[derive(Component)]
+pub struct CrackOverlay;
+
+#[derive(Resource)]
+pub struct CrackMaterials(pub Vec<Handle<StandardMaterial>>);
+
+/// The crack image for `stage` (0 to `CRACK_STAGES - 1`): dark lines on a clear
+/// background, each stage adding to the previous one.
+pub fn crack_image(stage: usize) -> RgbaImage {
+    let mut image = RgbaImage::new(CRACK_SIZE, CRACK_SIZE);
+    let steps = (stage + 1) * CRACK_SIZE as usize / CRACK_STAGES;
+
+    let mut seed = 0x9e37_79b9_u32;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+    for _ in 0..CRACK_LINES {
+        let (mut x, mut y) = (CRACK_SIZE as i32 / 2, CRACK_SIZE as i32 / 2);
+        let heading = random() % 8;
+        // Walk the whole line so every stage draws the same lines, just longer
+        for step in 0..CRACK_SIZE as usize {
+            // Mostly keep going the same way, sometimes veer off by 45 degrees
+            let turn = match random() % 4 {
+                0 => 7,
+                1 => 1,
+                _ => 0,
+            };
+            let (dx, dy) = [
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+            ][((heading + turn) % 8) as usize];
+            x = (x + dx).clamp(0, CRACK_SIZE as i32 - 1);
+            y = (y + dy).clamp(0, CRACK_SIZE as i32 - 1);
+            if step < steps {
+                image.put_pixel(x as u32, y as u32, Rgba([20, 20, 20, 200]));
+            }
+        }
+    }
+    image
+}
+
+pub fn setup_crack_overlay(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let stages = (0..CRACK_STAGES)
+        .map(|stage| {
+            let crack = crack_image(stage);
+            let mut texture = Image::new(
+                Extent3d {
+                    width: crack.width(),
+                    height: crack.height(),
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                crack.into_raw(),
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::RENDER_WORLD,
+            );
+            texture.sampler = ImageSampler::nearest();
+            materials.add(StandardMaterial {
+                base_color_texture: Some(images.add(texture)),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    commands.spawn((
+        CrackOverlay,
+        Mesh3d(meshes.add(Cuboid::from_length(OVERLAY_SIZE))),
+        MeshMaterial3d(stages[0].clone()),
+        Transform::default(),
+        Visibility::Hidden,
+        NotShadowCaster,
+    ));
+    commands.insert_resource(CrackMaterials(stages));
+}
+
+/// Shows the crack stage of the latest `BlockDamagedEvent`, and hides the overlay
+/// on frames without one.
+pub fn update_crack_overlay(
+    mut ev_damaged: EventReader<BlockDamagedEvent>,
+    crack_materials: Res<CrackMaterials>,
+    mut overlays: Query<
+        (
+            &mut MeshMaterial3d<StandardMaterial>,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        With<CrackOverlay>,
+    >,
+) {
+    let damaged = ev_damaged.read().last();
+    for (mut material, mut transform, mut visibility) in &mut overlays {
+        let Some(event) = damaged else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+
+        let stage = ((event.progress * CRACK_STAGES as f32) as usize).min(CRACK_STAGES - 1);
+        if material.0 != crack_materials.0[stage] {
+            material.0 = crack_materials.0[stage].clone();
+        }
+        transform.translation = event.position.as_vec3() + Vec3::splat(0.5);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cracks_grow_with_each_stage() {
+        let cracked = |stage| crack_image(stage).pixels().filter(|p| p[3] > 0).count();
+        for stage in 1..CRACK_STAGES {
+            assert!(cracked(stage) > cracked(stage - 1));
+        }
+        // Later stages only add to earlier ones
+        let first = crack_image(0);
+        let last = crack_image(CRACK_STAGES - 1);
+        assert!(
+            first
+                .enumerate_pixels()
+                .all(|(x, y, p)| p[3] == 0 || last.get_pixel(x, y)[3] > 0)
+        );
+    }
+}
@@ -0,0 +1,218 @@
+pub mod crack;
+
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::events::{BlockPlacedEvent, BlockRemovedEvent};
+use crate::world::block::BlockType;
+use crate::world::chunk::ChunkMap;
+use crate::world::model::solid_box_at;
+use crack::{setup_crack_overlay, update_crack_overlay};
+
+const BREAK_PARTICLES: u32 = 16;
+const PLACE_PUFFS: u32 = 8;
+const PARTICLE_SIZE: f32 = 0.12;
+const PUFF_SIZE: f32 = 0.2;
+const GRAVITY: f32 = 20.0;
+
+/// A short-lived cube flying out of a broken or placed block. It shrinks away
+/// over its lifetime.
+#[derive(Component)]
+pub struct Particle {
+    pub velocity: Vec3,
+    pub gravity: f32,
+    pub age: f32,
+    pub lifetime: f32,
+    pub size: f32,
+}
+
+/// One mesh for every particle and one material per block color.
+#[derive(Resource, Default)]
+struct ParticleAssets {
+    mesh: Handle<Mesh>,
+    materials: HashMap<(BlockType, bool), Handle<StandardMaterial>>,
+}
+
+impl ParticleAssets {
+    /// The material for particles of `block`, lightened for puffs.
+    fn material(
+        &mut self,
+        block: BlockType,
+        puff: bool,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        self.materials
+            .entry((block, puff))
+            .or_insert_with(|| {
+                let color = block.color();
+                let (color, alpha_mode) = if puff {
+                    (
+                        color.mix(&Color::WHITE, 0.6).with_alpha(0.7),
+                        AlphaMode::Blend,
+                    )
+                } else if color.alpha() < 1.0 {
+                    (color, AlphaMode::Blend)
+                } else {
+                    (color, AlphaMode::Opaque)
+                };
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    alpha_mode,
+                    perceptual_roughness: 0.9,
+                    ..default()
+                })
+            })
+            .clone()
+    }
+}
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticleAssets>()
+            .add_systems(Startup, (setup_particle_assets, setup_crack_overlay))
+            .add_systems(
+                Update,
+                (
+                    spawn_break_particles,
+                    spawn_place_puffs,
+                    update_particles,
+                    update_crack_overlay.after(crate::interaction::raycast::block_interaction),
+                ),
+            );
+    }
+}
+
+fn setup_particle_assets(mut assets: ResMut<ParticleAssets>, mut meshes: ResMut<Assets<Mesh>>) {
+    assets.mesh = meshes.add(Cuboid::from_length(1.0));
+}
+
+/// Deterministic pseudo-random value in `0..1` for particle `index` of an effect
+/// at `pos`.
+fn jitter(pos: IVec3, index: u32, salt: u32) -> f32 {
+    let mut h = (pos.x as u32).wrapping_mul(0x27d4_eb2d)
+        ^ (pos.y as u32).wrapping_mul(0x1656_67b1)
+        ^ (pos.z as u32).wrapping_mul(0x9e37_79b9)
+        ^ index.wrapping_mul(0x85eb_ca6b)
+        ^ salt.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    (h & 0xffff) as f32 / 0x10000 as f32
+}
+
+fn spawn_break_particles(
+    mut commands: Commands,
+    mut ev_removed: EventReader<BlockRemovedEvent>,
+    mut assets: ResMut<ParticleAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for event in ev_removed.read() {
+        if event.block_type == BlockType::Air {
+            continue;
+        }
+        let material = assets.material(event.block_type, false, &mut materials);
+        let center = event.position.as_vec3() + Vec3::splat(0.5);
+
+        for i in 0..BREAK_PARTICLES {
+            let offset = Vec3::new(
+                jitter(event.position, i, 0),
+                jitter(event.position, i, 1),
+                jitter(event.position, i, 2),
+            ) - Vec3::splat(0.5);
+            let speed = 1.5 + 2.5 * jitter(event.position, i, 3);
+            let size = PARTICLE_SIZE * (0.6 + 0.8 * jitter(event.position, i, 4));
+            commands.spawn((
+                Particle {
+                    velocity: offset.normalize_or_zero() * speed + Vec3::Y * 3.0,
+                    gravity: GRAVITY,
+                    age: 0.0,
+                    lifetime: 0.5 + 0.5 * jitter(event.position, i, 5),
+                    size,
+                },
+                Mesh3d(assets.mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_translation(center + offset * 0.8).with_scale(Vec3::splat(size)),
+                NotShadowCaster,
+            ));
+        }
+    }
+}
+
+fn spawn_place_puffs(
+    mut commands: Commands,
+    mut ev_placed: EventReader<BlockPlacedEvent>,
+    mut assets: ResMut<ParticleAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for event in ev_placed.read() {
+        let material = assets.material(event.block_type, true, &mut materials);
+        let center = event.position.as_vec3() + Vec3::splat(0.5);
+
+        // A ring of puffs around the bottom of the new block, drifting outwards
+        for i in 0..PLACE_PUFFS {
+            let angle = (i as f32 + jitter(event.position, i, 0)) / PLACE_PUFFS as f32
+                * std::f32::consts::TAU;
+            let out = Vec3::new(angle.cos(), 0.0, angle.sin());
+            commands.spawn((
+                Particle {
+                    velocity: out * 0.8 + Vec3::Y * 0.6,
+                    gravity: 0.0,
+                    age: 0.0,
+                    lifetime: 0.4 + 0.2 * jitter(event.position, i, 1),
+                    size: PUFF_SIZE,
+                },
+                Mesh3d(assets.mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_translation(center + out * 0.6 - Vec3::Y * 0.4)
+                    .with_scale(Vec3::splat(PUFF_SIZE)),
+                NotShadowCaster,
+            ));
+        }
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    chunk_map: Res<ChunkMap>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform)>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut particle, mut transform) in &mut particles {
+        particle.age += dt;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        particle.velocity.y -= particle.gravity * dt;
+        let next = transform.translation + particle.velocity * dt;
+        if solid_box_at(next, &chunk_map).is_some() {
+            // Settle on whatever it hits
+            particle.velocity = Vec3::ZERO;
+        } else {
+            transform.translation = next;
+        }
+        let remaining = 1.0 - particle.age / particle.lifetime;
+        transform.scale = Vec3::splat(particle.size * remaining.sqrt());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_is_spread_over_the_unit_range() {
+        let values: Vec<f32> = (0..256)
+            .map(|i| jitter(IVec3::new(3, 20, -7), i, 0))
+            .collect();
+        assert!(values.iter().all(|v| (0.0..1.0).contains(v)));
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        assert!((mean - 0.5).abs() < 0.1);
+        assert_ne!(jitter(IVec3::ZERO, 0, 0), jitter(IVec3::ZERO, 0, 1));
+    }
+}
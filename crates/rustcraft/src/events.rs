@@ -2,6 +2,7 @@ use bevy::prelude::*;
 
 use crate::inventory::ItemStack;
 use crate::player::camera::{GameMode, Location};
+use crate::player::health::DamageCause;
 use crate::world::block::BlockType;
 
 // --- Events ---
@@ -20,6 +21,15 @@ pub struct BlockRemovedEvent {
     pub player: Location,
 }
 
+/// Sent every frame a block is being mined, with how far along it is (0 to 1).
+#[derive(Event)]
+pub struct BlockDamagedEvent {
+    pub position: IVec3,
+    pub block_type: BlockType,
+    pub progress: f32,
+    pub player: Location,
+}
+
 #[derive(Event)]
 pub struct PlayerMovedEvent {
     pub old_position: Vec3,
@@ -65,18 +75,62 @@ pub struct ItemsCollectedEvent {
     pub player: Location,
 }
 
+/// Sent when the sun rises (`is_day`) or sets.
+#[derive(Event)]
+pub struct DayNightChangedEvent {
+    pub is_day: bool,
+    /// The day it happened on, counting from 0 when the world was created.
+    pub day: u64,
+}
+
+/// Sent when the player starts (`sprinting`) or stops sprinting.
+#[derive(Event)]
+pub struct SprintToggledEvent {
+    pub sprinting: bool,
+    pub player: Location,
+}
+
+/// Sent when the player starts (`sneaking`) or stops sneaking.
+#[derive(Event)]
+pub struct SneakToggledEvent {
+    pub sneaking: bool,
+    pub player: Location,
+}
+
+/// Sent when the player loses `amount` of health, leaving them with `health`.
+#[derive(Event)]
+pub struct PlayerDamagedEvent {
+    pub amount: f32,
+    pub cause: DamageCause,
+    pub health: f32,
+    pub player: Location,
+}
+
+/// Sent when the player's health runs out.
+#[derive(Event)]
+pub struct PlayerDiedEvent {
+    pub cause: DamageCause,
+    pub player: Location,
+}
+
 // --- Plugin trait ---
 
 #[allow(unused_variables)]
 pub trait RustcraftPlugin: Send + Sync + 'static {
     fn on_block_placed(&self, event: &BlockPlacedEvent) {}
     fn on_block_removed(&self, event: &BlockRemovedEvent) {}
+    fn on_block_damaged(&self, event: &BlockDamagedEvent) {}
     fn on_player_moved(&self, event: &PlayerMovedEvent) {}
     fn on_gamemode_changed(&self, event: &GameModeChangedEvent) {}
     fn on_inventory_picked_up(&self, event: &InventoryPickedUpEvent) {}
     fn on_inventory_dropped(&self, event: &InventoryDroppedEvent) {}
     fn on_item_dropped_to_world(&self, event: &ItemDroppedToWorldEvent) {}
     fn on_items_collected(&self, event: &ItemsCollectedEvent) {}
+    fn on_day_night_changed(&self, event: &DayNightChangedEvent) {}
+    fn on_sprint_toggled(&self, event: &SprintToggledEvent) {}
+    fn on_sneak_toggled(&self, event: &SneakToggledEvent) {}
+    fn on_player_damaged(&self, event: &PlayerDamagedEvent) {}
+    fn on_player_died(&self, event: &PlayerDiedEvent) {}
 }
 
 // --- Registry ---
@@ -110,6 +164,17 @@ fn dispatch_block_removed(
     }
 }
 
+fn dispatch_block_damaged(
+    mut reader: EventReader<BlockDamagedEvent>,
+    registry: Res<PluginRegistry>,
+) {
+    for event in reader.read() {
+        for plugin in &registry.plugins {
+            plugin.on_block_damaged(event);
+        }
+    }
+}
+
 fn dispatch_player_moved(
     mut reader: EventReader<PlayerMovedEvent>,
     registry: Res<PluginRegistry>,
@@ -176,6 +241,58 @@ fn dispatch_items_collected(
     }
 }
 
+fn dispatch_day_night_changed(
+    mut reader: EventReader<DayNightChangedEvent>,
+    registry: Res<PluginRegistry>,
+) {
+    for event in reader.read() {
+        for plugin in &registry.plugins {
+            plugin.on_day_night_changed(event);
+        }
+    }
+}
+
+fn dispatch_sprint_toggled(
+    mut reader: EventReader<SprintToggledEvent>,
+    registry: Res<PluginRegistry>,
+) {
+    for event in reader.read() {
+        for plugin in &registry.plugins {
+            plugin.on_sprint_toggled(event);
+        }
+    }
+}
+
+fn dispatch_sneak_toggled(
+    mut reader: EventReader<SneakToggledEvent>,
+    registry: Res<PluginRegistry>,
+) {
+    for event in reader.read() {
+        for plugin in &registry.plugins {
+            plugin.on_sneak_toggled(event);
+        }
+    }
+}
+
+fn dispatch_player_damaged(
+    mut reader: EventReader<PlayerDamagedEvent>,
+    registry: Res<PluginRegistry>,
+) {
+    for event in reader.read() {
+        for plugin in &registry.plugins {
+            plugin.on_player_damaged(event);
+        }
+    }
+}
+
+fn dispatch_player_died(mut reader: EventReader<PlayerDiedEvent>, registry: Res<PluginRegistry>) {
+    for event in reader.read() {
+        for plugin in &registry.plugins {
+            plugin.on_player_died(event);
+        }
+    }
+}
+
 // --- EventsPlugin builder ---
 
 pub struct EventsPlugin {
@@ -202,23 +319,35 @@ impl Plugin for EventsPlugin {
 
         app.add_event::<BlockPlacedEvent>()
             .add_event::<BlockRemovedEvent>()
+            .add_event::<BlockDamagedEvent>()
             .add_event::<PlayerMovedEvent>()
             .add_event::<GameModeChangedEvent>()
             .add_event::<InventoryPickedUpEvent>()
             .add_event::<InventoryDroppedEvent>()
             .add_event::<ItemDroppedToWorldEvent>()
             .add_event::<ItemsCollectedEvent>()
+            .add_event::<DayNightChangedEvent>()
+            .add_event::<SprintToggledEvent>()
+            .add_event::<SneakToggledEvent>()
+            .add_event::<PlayerDamagedEvent>()
+            .add_event::<PlayerDiedEvent>()
             .add_systems(
                 Update,
                 (
                     dispatch_block_placed,
                     dispatch_block_removed,
+                    dispatch_block_damaged,
                     dispatch_player_moved,
                     dispatch_gamemode_changed,
                     dispatch_inventory_picked_up,
                     dispatch_inventory_dropped,
                     dispatch_item_dropped_to_world,
                     dispatch_items_collected,
+                    dispatch_day_night_changed,
+                    dispatch_sprint_toggled,
+                    dispatch_sneak_toggled,
+                    dispatch_player_damaged,
+                    dispatch_player_died,
                 ),
             );
     }
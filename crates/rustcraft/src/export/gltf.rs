@@ -0,0 +1,235 @@
+use image::{ImageFormat, RgbaImage};
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::path::Path;
+
+use super::{ExportError, ExportMesh, pass_name};
+use crate::render::mesh::MeshPass;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const NEAREST: u32 = 9728;
+const CLAMP_TO_EDGE: u32 = 33071;
+
+/// The binary buffer and the buffer views and accessors pointing into it.
+#[derive(Default)]
+struct Buffers {
+    bin: Vec<u8>,
+    views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl Buffers {
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(bytes);
+        pad(&mut self.bin, 0);
+        let target = target
+            .map(|target| format!(",\"target\":{}", target))
+            .unwrap_or_default();
+        self.views.push(format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{}{}}}",
+            offset,
+            bytes.len(),
+            target
+        ));
+        self.views.len() - 1
+    }
+
+    /// Adds an accessor for `values`, `N` floats each, and returns its index.
+    fn floats<const N: usize>(&mut self, values: &[[f32; N]], bounds: bool) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let view = self.view(&bytes, Some(ARRAY_BUFFER));
+        let mut accessor = format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"VEC{}\"",
+            view,
+            FLOAT,
+            values.len(),
+            N
+        );
+        // glTF requires the bounds of positions
+        if bounds {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+            for value in values {
+                for i in 0..N {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+            let _ = write!(accessor, ",\"min\":{:?},\"max\":{:?}", min, max);
+        }
+        accessor.push('}');
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn indices(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+        self.accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"SCALAR\"}}",
+            view,
+            UNSIGNED_INT,
+            indices.len()
+        ));
+        self.accessors.len() - 1
+    }
+}
+
+/// Pads `bytes` to a multiple of four, as GLB chunks and buffer views must be.
+fn pad(bytes: &mut Vec<u8>, with: u8) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(with);
+    }
+}
+
+fn material(pass: MeshPass, textured: bool) -> String {
+    let alpha_mode = match (pass, textured) {
+        (MeshPass::Translucent, _) => "\"alphaMode\":\"BLEND\"",
+        (MeshPass::Opaque, true) => "\"alphaMode\":\"MASK\",\"alphaCutoff\":0.5",
+        (MeshPass::Opaque, false) => "\"alphaMode\":\"OPAQUE\"",
+    };
+    let texture = if textured {
+        "\"baseColorTexture\":{\"index\":0},"
+    } else {
+        ""
+    };
+    format!(
+        "{{\"name\":\"{}\",\"doubleSided\":true,{},\"pbrMetallicRoughness\":{{{}\"metallicFactor\":0,\"roughnessFactor\":0.9}}}}",
+        pass_name(pass),
+        alpha_mode,
+        texture
+    )
+}
+
+/// Encodes `meshes` as a binary glTF document, with `atlas` embedded as the texture
+/// of every material if given.
+fn encode_glb(meshes: &[ExportMesh], atlas: Option<&RgbaImage>) -> Result<Vec<u8>, ExportError> {
+    let mut buffers = Buffers::default();
+    let mut primitives = Vec::new();
+    let mut materials = Vec::new();
+    for mesh in meshes {
+        let mut attributes = format!(
+            "\"POSITION\":{},\"NORMAL\":{}",
+            buffers.floats(&mesh.positions, true),
+            buffers.floats(&mesh.normals, false)
+        );
+        if !mesh.uvs.is_empty() {
+            let _ = write!(
+                attributes,
+                ",\"TEXCOORD_0\":{}",
+                buffers.floats(&mesh.uvs, false)
+            );
+        }
+        let _ = write!(
+            attributes,
+            ",\"COLOR_0\":{}",
+            buffers.floats(&mesh.colors, false)
+        );
+        primitives.push(format!(
+            "{{\"attributes\":{{{}}},\"indices\":{},\"material\":{}}}",
+            attributes,
+            buffers.indices(&mesh.indices),
+            materials.len()
+        ));
+        materials.push(material(mesh.pass, atlas.is_some()));
+    }
+
+    let mut textures = String::new();
+    if let Some(atlas) = atlas {
+        let mut png = Vec::new();
+        atlas.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        let view = buffers.view(&png, None);
+        textures = format!(
+            ",\"images\":[{{\"bufferView\":{},\"mimeType\":\"image/png\"}}],\"samplers\":[{{\"magFilter\":{},\"minFilter\":{},\"wrapS\":{},\"wrapT\":{}}}],\"textures\":[{{\"source\":0,\"sampler\":0}}]",
+            view, NEAREST, NEAREST, CLAMP_TO_EDGE, CLAMP_TO_EDGE
+        );
+    }
+
+    let mut json = format!(
+        "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"rustcraft\"}},\"scene\":0,\"scenes\":[{{\"nodes\":[0]}}],\"nodes\":[{{\"name\":\"world\",\"mesh\":0}}],\"meshes\":[{{\"primitives\":[{}]}}],\"materials\":[{}],\"accessors\":[{}],\"bufferViews\":[{}],\"buffers\":[{{\"byteLength\":{}}}]{}}}",
+        primitives.join(","),
+        materials.join(","),
+        buffers.accessors.join(","),
+        buffers.views.join(","),
+        buffers.bin.len(),
+        textures
+    )
+    .into_bytes();
+    pad(&mut json, b' ');
+
+    let mut glb = Vec::with_capacity(12 + 8 + json.len() + 8 + buffers.bin.len());
+    let length = 12 + 8 + json.len() + 8 + buffers.bin.len();
+    for word in [GLB_MAGIC, GLB_VERSION, length as u32] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(buffers.bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+    glb.extend_from_slice(&buffers.bin);
+    Ok(glb)
+}
+
+pub fn write_glb(
+    path: &Path,
+    meshes: &[ExportMesh],
+    atlas: Option<&RgbaImage>,
+) -> Result<(), ExportError> {
+    std::fs::write(path, encode_glb(meshes, atlas)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn glb_chunks_are_aligned_and_sized() {
+        let mesh = ExportMesh {
+            pass: MeshPass::Opaque,
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            normals: vec![[0.0, 0.0, 1.0]; 3],
+            uvs: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+            colors: vec![[1.0; 4]; 3],
+            indices: vec![0, 1, 2],
+        };
+        let atlas = RgbaImage::new(2, 2);
+        let glb = encode_glb(&[mesh], Some(&atlas)).unwrap();
+
+        assert_eq!(word(&glb, 0), GLB_MAGIC);
+        assert_eq!(word(&glb, 4), GLB_VERSION);
+        assert_eq!(word(&glb, 8) as usize, glb.len());
+
+        let json_len = word(&glb, 12) as usize;
+        assert_eq!(word(&glb, 16), CHUNK_JSON);
+        assert_eq!(json_len % 4, 0);
+        let json = std::str::from_utf8(&glb[20..20 + json_len]).unwrap();
+        assert!(json.contains("\"POSITION\":0"));
+        assert!(json.contains("\"min\":[0.0, 0.0, 0.0],\"max\":[1.0, 1.0, 0.0]"));
+        assert!(json.contains("\"mimeType\":\"image/png\""));
+
+        let bin = 20 + json_len;
+        let bin_len = word(&glb, bin) as usize;
+        assert_eq!(word(&glb, bin + 4), CHUNK_BIN);
+        assert_eq!(bin_len % 4, 0);
+        assert_eq!(bin + 8 + bin_len, glb.len());
+    }
+}
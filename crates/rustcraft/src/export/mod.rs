@@ -0,0 +1,315 @@
+pub mod gltf;
+pub mod obj;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::render::atlas::BlockAtlas;
+use crate::render::lod::ChunkLods;
+use crate::render::mesh::{MeshPass, MeshSettings, Mesher};
+use crate::world::chunk::{CHUNK_SIZE, ChunkMap, ChunkPos};
+
+/// How exported blocks get their color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Coloring {
+    /// UVs into the block atlas, which is saved with the model. Faces aren't merged
+    /// since the atlas tiles can't repeat across a merged face without the game's
+    /// shader.
+    #[default]
+    Textured,
+    /// Each vertex carries the average color of its block's tile, so no texture is
+    /// needed and faces are merged.
+    VertexColors,
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    Image(image::ImageError),
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Io(err) => write!(f, "{}", err),
+            ExportError::Image(err) => write!(f, "{}", err),
+            ExportError::UnknownFormat(path) => write!(
+                f,
+                "don't know how to write {:?} (expected a .glb or .obj file)",
+                path
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        ExportError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for ExportError {
+    fn from(err: image::ImageError) -> Self {
+        ExportError::Image(err)
+    }
+}
+
+/// The blocks of one `MeshPass` across the exported region, in world coordinates.
+pub struct ExportMesh {
+    pub pass: MeshPass,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// UVs into the block atlas; empty with `Coloring::VertexColors`.
+    pub uvs: Vec<[f32; 2]>,
+    /// Linear vertex colors: the ambient occlusion shade, times the block's color
+    /// with `Coloring::VertexColors`.
+    pub colors: Vec<[f32; 4]>,
+    pub indices: Vec<u32>,
+}
+
+impl ExportMesh {
+    fn new(pass: MeshPass) -> Self {
+        Self {
+            pass,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Appends a chunk mesh built by `MeshSettings::build`, moved to `offset`.
+    fn append(
+        &mut self,
+        mesh: &Mesh,
+        offset: Vec3,
+        atlas: &BlockAtlas,
+        coloring: Coloring,
+        tile_colors: &mut HashMap<[u32; 2], [f32; 4]>,
+    ) {
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+            Some(VertexAttributeValues::Float32x2(uvs)),
+            Some(VertexAttributeValues::Float32x2(tiles)),
+            Some(VertexAttributeValues::Float32x4(shades)),
+            Some(Indices::U32(indices)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            mesh.attribute(Mesh::ATTRIBUTE_UV_0),
+            mesh.attribute(Mesh::ATTRIBUTE_UV_1),
+            mesh.attribute(Mesh::ATTRIBUTE_COLOR),
+            mesh.indices(),
+        )
+        else {
+            return;
+        };
+
+        let base = self.positions.len() as u32;
+        self.positions.extend(
+            positions
+                .iter()
+                .map(|p| (Vec3::from_array(*p) + offset).to_array()),
+        );
+        self.normals.extend_from_slice(normals);
+        self.indices.extend(indices.iter().map(|i| base + i));
+
+        let tile_size = atlas.tile_size();
+        for ((uv, tile), shade) in uvs.iter().zip(tiles).zip(shades) {
+            match coloring {
+                Coloring::Textured => {
+                    self.uvs
+                        .push((Vec2::from(*tile) + Vec2::from(*uv) * tile_size).to_array());
+                    self.colors.push(*shade);
+                }
+                Coloring::VertexColors => {
+                    let color = *tile_colors
+                        .entry(tile.map(f32::to_bits))
+                        .or_insert_with(|| tile_color(atlas, Vec2::from(*tile)));
+                    self.colors.push([
+                        color[0] * shade[0],
+                        color[1] * shade[1],
+                        color[2] * shade[2],
+                        color[3],
+                    ]);
+                }
+            }
+        }
+    }
+}
+
+/// Average linear color of the visible pixels of the atlas tile at `origin`.
+fn tile_color(atlas: &BlockAtlas, origin: Vec2) -> [f32; 4] {
+    let size =
+        atlas.tile_size() * Vec2::new(atlas.image.width() as f32, atlas.image.height() as f32);
+    let start = origin * Vec2::new(atlas.image.width() as f32, atlas.image.height() as f32);
+    let (x0, y0) = (start.x.round() as u32, start.y.round() as u32);
+
+    let mut sum = LinearRgba::NONE;
+    let mut count = 0;
+    for y in y0..y0 + size.y.round() as u32 {
+        for x in x0..x0 + size.x.round() as u32 {
+            let [r, g, b, a] = atlas.image.get_pixel(x, y).0;
+            if a > 0 {
+                sum += Color::srgba_u8(r, g, b, a).to_linear();
+                count += 1;
+            }
+        }
+    }
+    if count == 0 {
+        return [1.0; 4];
+    }
+    (sum / count as f32).to_f32_array()
+}
+
+/// Name of the material (and object) holding `pass`.
+fn pass_name(pass: MeshPass) -> &'static str {
+    match pass {
+        MeshPass::Opaque => "opaque",
+        MeshPass::Translucent => "translucent",
+    }
+}
+
+/// Meshes the chunks from `min` to `max` (inclusive) the way the game does at full
+/// detail and joins them into one mesh per pass. Blocks outside the region count as
+/// air, so its sides are closed. Passes without any faces are left out.
+pub fn build_region(
+    chunk_map: &ChunkMap,
+    min: ChunkPos,
+    max: ChunkPos,
+    atlas: &BlockAtlas,
+    coloring: Coloring,
+) -> Vec<ExportMesh> {
+    let settings = MeshSettings {
+        mesher: match coloring {
+            Coloring::Textured => Mesher::Naive,
+            Coloring::VertexColors => Mesher::Greedy,
+        },
+        ambient_occlusion: true,
+        lod: false,
+    };
+    let mut region = ChunkMap::default();
+    for (pos, chunk) in &chunk_map.chunks {
+        if (min.0..=max.0).contains(&pos.0) && (min.1..=max.1).contains(&pos.1) {
+            region.chunks.insert(*pos, chunk.clone());
+        }
+    }
+
+    let mut opaque = ExportMesh::new(MeshPass::Opaque);
+    let mut translucent = ExportMesh::new(MeshPass::Translucent);
+    let mut tile_colors = HashMap::new();
+    let mut positions: Vec<&ChunkPos> = region.chunks.keys().collect();
+    positions.sort_by_key(|pos| (pos.0, pos.1));
+    for pos in positions {
+        let meshes = settings.build(*pos, &region, &ChunkLods::default(), atlas);
+        let offset = Vec3::new(
+            (pos.0 * CHUNK_SIZE as i32) as f32,
+            0.0,
+            (pos.1 * CHUNK_SIZE as i32) as f32,
+        );
+        for export in [&mut opaque, &mut translucent] {
+            export.append(
+                meshes.get(export.pass),
+                offset,
+                atlas,
+                coloring,
+                &mut tile_colors,
+            );
+        }
+    }
+
+    [opaque, translucent]
+        .into_iter()
+        .filter(|mesh| !mesh.indices.is_empty())
+        .collect()
+}
+
+/// Writes `meshes` to `path` as binary glTF (`.glb`) or Wavefront OBJ (`.obj`),
+/// picked by its extension. Textured OBJ files get their materials and the atlas
+/// saved next to them.
+pub fn write(
+    path: &Path,
+    meshes: &[ExportMesh],
+    atlas: &BlockAtlas,
+    coloring: Coloring,
+) -> Result<(), ExportError> {
+    let atlas = (coloring == Coloring::Textured).then_some(&atlas.image);
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("glb") => gltf::write_glb(path, meshes, atlas),
+        Some("obj") => obj::write_obj(path, meshes, atlas),
+        _ => Err(ExportError::UnknownFormat(path.to_path_buf())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block::BlockType;
+    use crate::world::chunk::Chunk;
+
+    #[test]
+    fn region_is_meshed_in_world_coordinates() {
+        let mut chunk_map = ChunkMap::default();
+        for pos in [ChunkPos(0, 0), ChunkPos(1, 0), ChunkPos(5, 5)] {
+            let mut chunk = Chunk::new();
+            chunk.set_block(0, 0, 0, BlockType::Stone);
+            chunk.set_block(1, 0, 0, BlockType::Water);
+            chunk_map.chunks.insert(pos, chunk);
+        }
+        let atlas = BlockAtlas::from_colors();
+
+        let meshes = build_region(
+            &chunk_map,
+            ChunkPos(0, 0),
+            ChunkPos(1, 0),
+            &atlas,
+            Coloring::Textured,
+        );
+        assert_eq!(meshes.len(), 2);
+        let opaque = &meshes[0];
+        // Two stone cubes; the one at (5, 5) is outside the region. Each shows all
+        // six faces, the one against the water included
+        assert_eq!(opaque.positions.len(), 2 * 6 * 4);
+        assert!(opaque.positions.iter().any(|p| p[0] == 17.0));
+        assert!(opaque.positions.iter().all(|p| p[0] <= 18.0 && p[2] <= 1.0));
+        // UVs land inside the stone's atlas tile
+        let tile = atlas.tile_origin(BlockType::Stone, crate::render::atlas::BlockFace::Top);
+        let tile_size = atlas.tile_size();
+        assert!(opaque.uvs.iter().all(|uv| {
+            let uv = Vec2::from(*uv) - tile;
+            uv.cmpge(Vec2::ZERO).all() && uv.cmple(tile_size).all()
+        }));
+
+        let colored = build_region(
+            &chunk_map,
+            ChunkPos(0, 0),
+            ChunkPos(1, 0),
+            &atlas,
+            Coloring::VertexColors,
+        );
+        assert!(colored[0].uvs.is_empty());
+        // Stone gray, give or take the atlas' 8-bit rounding
+        let stone = BlockType::Stone.color().to_linear().red;
+        assert!(
+            colored[0]
+                .colors
+                .iter()
+                .all(|c| (c[0] - stone).abs() < 0.01)
+        );
+        // Water keeps its transparency
+        assert!(colored[1].colors.iter().all(|c| c[3] < 1.0));
+    }
+}
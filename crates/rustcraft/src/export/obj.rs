@@ -0,0 +1,140 @@
+use image::RgbaImage;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use super::{ExportError, ExportMesh, pass_name};
+use crate::render::mesh::MeshPass;
+
+/// Converts a linear color channel back to sRGB, which OBJ viewers expect.
+fn to_srgb(linear: f32) -> f32 {
+    bevy::color::Color::linear_rgb(linear, 0.0, 0.0)
+        .to_srgba()
+        .red
+}
+
+/// The OBJ text for `meshes`, one object per pass using the materials of
+/// `encode_mtl`. Vertex colors are written (as sRGB) only when there is no texture.
+fn encode_obj(meshes: &[ExportMesh], mtl_name: &str, textured: bool) -> String {
+    let mut obj = format!("# rustcraft\nmtllib {}\n", mtl_name);
+    // Indices in OBJ are 1-based and count every vertex written so far
+    let mut base = 1;
+    for mesh in meshes {
+        let name = pass_name(mesh.pass);
+        let _ = writeln!(obj, "o {}\nusemtl {}", name, name);
+        for (p, c) in mesh.positions.iter().zip(&mesh.colors) {
+            if textured {
+                let _ = writeln!(obj, "v {} {} {}", p[0], p[1], p[2]);
+            } else {
+                let _ = writeln!(
+                    obj,
+                    "v {} {} {} {:.3} {:.3} {:.3}",
+                    p[0],
+                    p[1],
+                    p[2],
+                    to_srgb(c[0]),
+                    to_srgb(c[1]),
+                    to_srgb(c[2])
+                );
+            }
+        }
+        for uv in &mesh.uvs {
+            // OBJ puts the texture origin at the bottom left
+            let _ = writeln!(obj, "vt {} {}", uv[0], 1.0 - uv[1]);
+        }
+        for n in &mesh.normals {
+            let _ = writeln!(obj, "vn {} {} {}", n[0], n[1], n[2]);
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            obj.push('f');
+            for i in triangle {
+                let i = base + i;
+                if textured {
+                    let _ = write!(obj, " {}/{}/{}", i, i, i);
+                } else {
+                    let _ = write!(obj, " {}//{}", i, i);
+                }
+            }
+            obj.push('\n');
+        }
+        base += mesh.positions.len() as u32;
+    }
+    obj
+}
+
+/// The materials for `encode_obj`, sampling `texture` if given.
+fn encode_mtl(texture: Option<&str>) -> String {
+    let mut mtl = String::from("# rustcraft\n");
+    for pass in [MeshPass::Opaque, MeshPass::Translucent] {
+        let _ = writeln!(mtl, "newmtl {}\nKd 1 1 1", pass_name(pass));
+        if let Some(texture) = texture {
+            let _ = writeln!(mtl, "map_Kd {}", texture);
+            if pass == MeshPass::Translucent {
+                let _ = writeln!(mtl, "map_d {}", texture);
+            }
+        }
+    }
+    mtl
+}
+
+/// Writes `path` along with its `.mtl` file and, if given, `atlas` as
+/// `<name>_atlas.png`, all in the same directory.
+pub fn write_obj(
+    path: &Path,
+    meshes: &[ExportMesh],
+    atlas: Option<&RgbaImage>,
+) -> Result<(), ExportError> {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("world");
+    let mtl_name = format!("{}.mtl", stem);
+    let texture_name = format!("{}_atlas.png", stem);
+
+    if let Some(atlas) = atlas {
+        atlas.save(path.with_file_name(&texture_name))?;
+    }
+    let texture = atlas.is_some().then_some(texture_name.as_str());
+    std::fs::write(path.with_file_name(&mtl_name), encode_mtl(texture))?;
+    std::fs::write(path, encode_obj(meshes, &mtl_name, atlas.is_some()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn face_indices_continue_across_objects() {
+        let quad = |pass| ExportMesh {
+            pass,
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            normals: vec![[0.0, 0.0, 1.0]; 4],
+            uvs: vec![[0.0, 0.25]; 4],
+            colors: vec![[1.0; 4]; 4],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        };
+        let meshes = [quad(MeshPass::Opaque), quad(MeshPass::Translucent)];
+
+        let obj = encode_obj(&meshes, "world.mtl", true);
+        assert!(obj.contains("mtllib world.mtl\n"));
+        assert!(obj.contains("o translucent\nusemtl translucent\n"));
+        assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 8);
+        assert!(obj.contains("vt 0 0.75\n"));
+        assert!(obj.contains("f 1/1/1 2/2/2 3/3/3\n"));
+        assert!(obj.contains("f 5/5/5 7/7/7 8/8/8\n"));
+
+        let colored = encode_obj(&meshes, "world.mtl", false);
+        assert!(colored.contains("v 1 0 0 1.000 1.000 1.000\n"));
+        assert!(colored.contains("f 5//5 6//6 7//7\n"));
+
+        let mtl = encode_mtl(Some("world_atlas.png"));
+        assert!(mtl.contains(
+            "newmtl translucent\nKd 1 1 1\nmap_Kd world_atlas.png\nmap_d world_atlas.png\n"
+        ));
+    }
+}
@@ -1,9 +1,14 @@
 pub mod raycast;
+pub mod selection;
 
 use bevy::prelude::*;
 use raycast::{
-    DebugOverlayVisible, DropKeyState, block_interaction, drop_active_item, spawn_crosshair,
-    spawn_debug_overlay, toggle_debug_overlay, update_debug_overlay,
+    DebugOverlayVisible, DropKeyState, MiningState, block_interaction, drop_active_item,
+    spawn_crosshair, spawn_debug_overlay, toggle_debug_overlay, update_debug_overlay,
+};
+use selection::{
+    SelectionSettings, TargetedBlock, draw_selection_outline, spawn_placement_preview,
+    update_placement_preview, update_targeted_block,
 };
 
 pub struct InteractionPlugin;
@@ -12,11 +17,26 @@ impl Plugin for InteractionPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<DebugOverlayVisible>()
             .init_resource::<DropKeyState>()
-            .add_systems(Startup, (spawn_crosshair, spawn_debug_overlay))
+            .init_resource::<MiningState>()
+            .init_resource::<TargetedBlock>()
+            .init_resource::<SelectionSettings>()
+            .add_systems(
+                Startup,
+                (
+                    spawn_crosshair,
+                    spawn_debug_overlay,
+                    spawn_placement_preview.after(crate::render::setup_block_textures),
+                ),
+            )
             .add_systems(
                 Update,
                 (
-                    block_interaction,
+                    (
+                        block_interaction,
+                        update_targeted_block,
+                        (draw_selection_outline, update_placement_preview),
+                    )
+                        .chain(),
                     drop_active_item,
                     toggle_debug_overlay,
                     update_debug_overlay,
@@ -1,19 +1,24 @@
 use bevy::prelude::*;
 
-use crate::events::{BlockPlacedEvent, BlockRemovedEvent, ItemDroppedToWorldEvent};
+use crate::events::{
+    BlockDamagedEvent, BlockPlacedEvent, BlockRemovedEvent, ItemDroppedToWorldEvent,
+};
 use crate::inventory::Inventory;
 use crate::player::camera::{FlyCam, GameMode, GameState, Player};
 use crate::world::block::BlockType;
 use crate::world::chunk::ChunkMap;
+use crate::world::structure::StructureIndex;
 
 const MAX_REACH: f32 = 8.0;
 
-struct RaycastHit {
-    block_pos: IVec3,
-    normal: IVec3,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaycastHit {
+    pub block_pos: IVec3,
+    /// Normal of the face the ray entered through.
+    pub normal: IVec3,
 }
 
-fn dda_raycast(origin: Vec3, direction: Vec3, chunk_map: &ChunkMap) -> Option<RaycastHit> {
+pub fn dda_raycast(origin: Vec3, direction: Vec3, chunk_map: &ChunkMap) -> Option<RaycastHit> {
     let dir = direction.normalize();
 
     let mut pos = IVec3::new(
@@ -106,17 +111,35 @@ fn dda_raycast(origin: Vec3, direction: Vec3, chunk_map: &ChunkMap) -> Option<Ra
     None
 }
 
+/// The block being mined in survival and how far along it is, from 0 to 1. Starts
+/// over when the button is released or the crosshair moves to another block.
+#[derive(Resource, Default)]
+pub struct MiningState {
+    target: Option<IVec3>,
+    progress: f32,
+}
+
 pub fn block_interaction(
     game_state: Res<GameState>,
     game_mode: Res<GameMode>,
     mouse: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    mut mining: ResMut<MiningState>,
     mut chunk_map: ResMut<ChunkMap>,
     camera_query: Query<(&Transform, &Player), With<FlyCam>>,
     mut inventory: ResMut<Inventory>,
     mut ev_placed: EventWriter<BlockPlacedEvent>,
     mut ev_removed: EventWriter<BlockRemovedEvent>,
+    mut ev_damaged: EventWriter<BlockDamagedEvent>,
     mut ev_item_drop: EventWriter<ItemDroppedToWorldEvent>,
 ) {
+    // Creative breaks blocks on click, survival by holding the button
+    let mining_held = *game_state == GameState::Playing
+        && *game_mode == GameMode::Survival
+        && mouse.pressed(MouseButton::Left);
+    if !mining_held {
+        mining.target = None;
+    }
     if *game_state != GameState::Playing {
         return;
     }
@@ -124,7 +147,7 @@ pub fn block_interaction(
     let left = mouse.just_pressed(MouseButton::Left);
     let right = mouse.just_pressed(MouseButton::Right);
 
-    if !left && !right {
+    if !left && !right && !mining_held {
         return;
     }
 
@@ -136,48 +159,78 @@ pub fn block_interaction(
     let origin = cam_transform.translation;
     let direction = cam_transform.forward().as_vec3();
 
-    if let Some(hit) = dda_raycast(origin, direction, &chunk_map) {
-        if left {
-            let old_block = chunk_map.get_block(hit.block_pos.x, hit.block_pos.y, hit.block_pos.z);
-            chunk_map.set_block(
-                hit.block_pos.x,
-                hit.block_pos.y,
-                hit.block_pos.z,
-                BlockType::Air,
-            );
-            ev_removed.send(BlockRemovedEvent {
+    let Some(hit) = dda_raycast(origin, direction, &chunk_map) else {
+        mining.target = None;
+        return;
+    };
+
+    let old_block = chunk_map.get_block(hit.block_pos.x, hit.block_pos.y, hit.block_pos.z);
+    let broken = if mining_held {
+        if mining.target != Some(hit.block_pos) {
+            mining.target = Some(hit.block_pos);
+            mining.progress = 0.0;
+        }
+        let break_time = old_block.break_time();
+        mining.progress = if break_time > 0.0 {
+            mining.progress + time.delta_secs() / break_time
+        } else {
+            1.0
+        };
+
+        if mining.progress < 1.0 {
+            ev_damaged.send(BlockDamagedEvent {
                 position: hit.block_pos,
                 block_type: old_block,
+                progress: mining.progress,
                 player: location,
             });
+        }
+        mining.progress >= 1.0
+    } else {
+        left && *game_mode == GameMode::Creative
+    };
+
+    if broken {
+        mining.target = None;
+        chunk_map.set_block(
+            hit.block_pos.x,
+            hit.block_pos.y,
+            hit.block_pos.z,
+            BlockType::Air,
+        );
+        ev_removed.send(BlockRemovedEvent {
+            position: hit.block_pos,
+            block_type: old_block,
+            player: location,
+        });
 
+        if *game_mode == GameMode::Survival {
+            let block_center = Vec3::new(
+                hit.block_pos.x as f32 + 0.5,
+                hit.block_pos.y as f32 + 0.5,
+                hit.block_pos.z as f32 + 0.5,
+            );
+            ev_item_drop.send(ItemDroppedToWorldEvent {
+                block_type: old_block,
+                count: 1,
+                position: block_center,
+                velocity: Vec3::new(0.0, 4.0, 0.0),
+                player: location,
+            });
+        }
+    } else if right {
+        // Food isn't an active block; it's eaten instead
+        if let Some(block) = inventory.active_block() {
+            let place_pos = hit.block_pos + hit.normal;
+            chunk_map.set_block(place_pos.x, place_pos.y, place_pos.z, block);
             if *game_mode == GameMode::Survival {
-                let block_center = Vec3::new(
-                    hit.block_pos.x as f32 + 0.5,
-                    hit.block_pos.y as f32 + 0.5,
-                    hit.block_pos.z as f32 + 0.5,
-                );
-                ev_item_drop.send(ItemDroppedToWorldEvent {
-                    block_type: old_block,
-                    count: 1,
-                    position: block_center,
-                    velocity: Vec3::new(0.0, 4.0, 0.0),
-                    player: location,
-                });
-            }
-        } else if right {
-            if let Some(block) = inventory.active_block() {
-                let place_pos = hit.block_pos + hit.normal;
-                chunk_map.set_block(place_pos.x, place_pos.y, place_pos.z, block);
-                if *game_mode == GameMode::Survival {
-                    inventory.consume_active();
-                }
-                ev_placed.send(BlockPlacedEvent {
-                    position: place_pos,
-                    block_type: block,
-                    player: location,
-                });
+                inventory.consume_active();
             }
+            ev_placed.send(BlockPlacedEvent {
+                position: place_pos,
+                block_type: block,
+                player: location,
+            });
         }
     }
 }
@@ -265,6 +318,7 @@ pub fn toggle_debug_overlay(
 pub fn update_debug_overlay(
     visible: Res<DebugOverlayVisible>,
     game_mode: Res<GameMode>,
+    structures: Res<StructureIndex>,
     camera_query: Query<(&Transform, &Player), With<FlyCam>>,
     mut text_query: Query<&mut Text, With<DebugOverlay>>,
 ) {
@@ -290,10 +344,20 @@ pub fn update_debug_overlay(
         _ => "East",
     };
 
+    let block_pos = IVec3::new(
+        pos.x.floor() as i32,
+        pos.y.floor() as i32,
+        pos.z.floor() as i32,
+    );
+    let structure = match structures.structure_at(block_pos) {
+        Some(structure) => format!("{:?}", structure.kind),
+        None => "None".to_string(),
+    };
+
     for mut text in &mut text_query {
         **text = format!(
-            "XYZ: {:.1} / {:.1} / {:.1}\nFacing: {} ({:.1} / {:.1})\nGameMode: {:?}",
-            pos.x, pos.y, pos.z, cardinal, yaw_deg, pitch_deg, *game_mode
+            "XYZ: {:.1} / {:.1} / {:.1}\nFacing: {} ({:.1} / {:.1})\nGameMode: {:?}\nStructure: {}",
+            pos.x, pos.y, pos.z, cardinal, yaw_deg, pitch_deg, *game_mode, structure
         );
     }
 }
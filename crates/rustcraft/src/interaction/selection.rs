@@ -0,0 +1,128 @@
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+
+use super::raycast::{RaycastHit, dda_raycast};
+use crate::inventory::Inventory;
+use crate::player::camera::{FlyCam, GameState};
+use crate::render::BlockTextures;
+use crate::render::atlas::BlockAtlas;
+use crate::render::mesh::build_block_mesh;
+use crate::world::block::BlockType;
+use crate::world::chunk::ChunkMap;
+
+const OUTLINE_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+/// The outline sits just outside the block so its faces don't hide the lines.
+const OUTLINE_SIZE: f32 = 1.005;
+/// The preview sits just inside its cell so it doesn't flicker against the face
+/// it is placed on.
+const PREVIEW_SIZE: f32 = 0.98;
+const PREVIEW_ALPHA: f32 = 0.45;
+
+/// The block the crosshair points at within reach, if any. Cleared while the game
+/// isn't being played.
+#[derive(Resource, Default)]
+pub struct TargetedBlock(pub Option<RaycastHit>);
+
+#[derive(Resource)]
+pub struct SelectionSettings {
+    /// Shows a see-through copy of the active hotbar block where it would be placed.
+    pub placement_preview: bool,
+}
+
+impl Default for SelectionSettings {
+    fn default() -> Self {
+        Self {
+            placement_preview: true,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PlacementPreview;
+
+pub fn update_targeted_block(
+    game_state: Res<GameState>,
+    chunk_map: Res<ChunkMap>,
+    camera_query: Query<&Transform, With<FlyCam>>,
+    mut targeted: ResMut<TargetedBlock>,
+) {
+    let hit = match camera_query.get_single() {
+        Ok(transform) if *game_state == GameState::Playing => dda_raycast(
+            transform.translation,
+            transform.forward().as_vec3(),
+            &chunk_map,
+        ),
+        _ => None,
+    };
+    if targeted.0 != hit {
+        targeted.0 = hit;
+    }
+}
+
+pub fn draw_selection_outline(targeted: Res<TargetedBlock>, mut gizmos: Gizmos) {
+    let Some(hit) = targeted.0 else {
+        return;
+    };
+    let center = hit.block_pos.as_vec3() + Vec3::splat(0.5);
+    gizmos.cuboid(
+        Transform::from_translation(center).with_scale(Vec3::splat(OUTLINE_SIZE)),
+        OUTLINE_COLOR,
+    );
+}
+
+pub fn spawn_placement_preview(
+    mut commands: Commands,
+    textures: Res<BlockTextures>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        PlacementPreview,
+        Mesh3d(meshes.add(Cuboid::from_length(PREVIEW_SIZE))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(1.0, 1.0, 1.0, PREVIEW_ALPHA),
+            base_color_texture: Some(textures.atlas.clone()),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+        NotShadowCaster,
+    ));
+}
+
+/// Moves the preview onto the targeted face and swaps its mesh when the active
+/// hotbar block changes.
+pub fn update_placement_preview(
+    settings: Res<SelectionSettings>,
+    targeted: Res<TargetedBlock>,
+    inventory: Res<Inventory>,
+    atlas: Res<BlockAtlas>,
+    mut preview_query: Query<(&Mesh3d, &mut Transform, &mut Visibility), With<PlacementPreview>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut shown_block: Local<Option<BlockType>>,
+) {
+    let Ok((mesh, mut transform, mut visibility)) = preview_query.get_single_mut() else {
+        return;
+    };
+
+    // The cell in front of the hit face is open since the ray came through it,
+    // unless the camera itself is inside the hit block
+    let target = targeted
+        .0
+        .filter(|hit| settings.placement_preview && hit.normal != IVec3::ZERO)
+        .map(|hit| hit.block_pos + hit.normal);
+    let (Some(place_pos), Some(block)) = (target, inventory.active_block()) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+
+    if *shown_block != Some(block) {
+        *shown_block = Some(block);
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            *mesh = build_block_mesh(block, &atlas, PREVIEW_SIZE);
+        }
+    }
+    transform.translation = place_pos.as_vec3() + Vec3::splat(0.5);
+    visibility.set_if_neq(Visibility::Inherited);
+}
@@ -37,6 +37,14 @@ impl Default for Inventory {
         slots[4] = Some(ItemStack::new(BlockType::Wood, 64));
         slots[5] = Some(ItemStack::new(BlockType::Leaves, 64));
         slots[6] = Some(ItemStack::new(BlockType::Water, 64));
+        slots[7] = Some(ItemStack::new(BlockType::Apple, 16));
+        slots[8] = Some(ItemStack::new(BlockType::Bread, 16));
+        slots[9] = Some(ItemStack::new(BlockType::StoneSlab, 64));
+        slots[10] = Some(ItemStack::new(BlockType::WoodStairs, 64));
+        slots[11] = Some(ItemStack::new(BlockType::Fence, 64));
+        slots[12] = Some(ItemStack::new(BlockType::GlassPane, 64));
+        slots[13] = Some(ItemStack::new(BlockType::Flower, 64));
+        slots[14] = Some(ItemStack::new(BlockType::TallGrass, 64));
         Self {
             slots,
             active_slot: 0,
@@ -45,10 +53,16 @@ impl Default for Inventory {
 }
 
 impl Inventory {
-    pub fn active_block(&self) -> Option<BlockType> {
+    /// Whatever is in the active slot, block or food.
+    pub fn active_item(&self) -> Option<BlockType> {
         self.slots[self.active_slot].map(|stack| stack.block)
     }
 
+    /// The block in the active slot, if it can be placed. Food is eaten, not placed.
+    pub fn active_block(&self) -> Option<BlockType> {
+        self.active_item().filter(|block| !block.is_food())
+    }
+
     /// Decrement the active slot count by 1. Removes the stack if count reaches 0.
     pub fn consume_active(&mut self) {
         if let Some(stack) = &mut self.slots[self.active_slot] {
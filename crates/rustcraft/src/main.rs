@@ -1,8 +1,11 @@
 mod avatar;
 mod dropped_item;
+mod effects;
 mod events;
+mod export;
 mod interaction;
 mod inventory;
+mod map;
 mod player;
 mod render;
 mod ui;
@@ -51,6 +54,22 @@ impl LogPlugin {
         );
     }
 
+    #[Event::BlockDamaged]
+    fn on_block_damaged(&self, event: &events::BlockDamagedEvent) {
+        // Sent every frame while mining, so keep it out of the default log
+        debug!(
+            "Player at ({:.1}, {:.1}, {:.1}) is mining {:?} at ({}, {}, {}): {:.0}%",
+            event.player.x,
+            event.player.y,
+            event.player.z,
+            event.block_type,
+            event.position.x,
+            event.position.y,
+            event.position.z,
+            event.progress * 100.0
+        );
+    }
+
     #[Event::ItemDroppedToWorld]
     fn on_item_dropped(&self, event: &events::ItemDroppedToWorldEvent) {
         info!(
@@ -65,9 +84,75 @@ impl LogPlugin {
             event.position.z
         );
     }
+
+    #[Event::SprintToggled]
+    fn on_sprint_toggled(&self, event: &events::SprintToggledEvent) {
+        info!(
+            "Player at ({:.1}, {:.1}, {:.1}) {} sprinting",
+            event.player.x,
+            event.player.y,
+            event.player.z,
+            if event.sprinting {
+                "started"
+            } else {
+                "stopped"
+            }
+        );
+    }
+
+    #[Event::SneakToggled]
+    fn on_sneak_toggled(&self, event: &events::SneakToggledEvent) {
+        info!(
+            "Player at ({:.1}, {:.1}, {:.1}) {} sneaking",
+            event.player.x,
+            event.player.y,
+            event.player.z,
+            if event.sneaking { "started" } else { "stopped" }
+        );
+    }
+
+    #[Event::PlayerDamaged]
+    fn on_player_damaged(&self, event: &events::PlayerDamagedEvent) {
+        info!(
+            "Player at ({:.1}, {:.1}, {:.1}) took {} damage from {:?}, {} health left",
+            event.player.x, event.player.y, event.player.z, event.amount, event.cause, event.health
+        );
+    }
+
+    #[Event::PlayerDied]
+    fn on_player_died(&self, event: &events::PlayerDiedEvent) {
+        info!(
+            "Player died at ({:.1}, {:.1}, {:.1}) from {:?}",
+            event.player.x, event.player.y, event.player.z, event.cause
+        );
+    }
+
+    #[Event::DayNightChanged]
+    fn on_day_night_changed(&self, event: &events::DayNightChangedEvent) {
+        if event.is_day {
+            info!("Day {} begins", event.day + 1);
+        } else {
+            info!("Night falls on day {}", event.day + 1);
+        }
+    }
 }
 
+/// `rustcraft export <file.glb|file.obj>` writes the generated world to a model file
+/// without opening a window. `--region <x0>,<z0>,<x1>,<z1>` picks the chunks to
+/// export (the whole starting world by default, and at most 64 by 64 chunks) and `--vertex-colors` colors blocks
+/// per vertex instead of saving the texture atlas. It takes the same world and
+/// texture flags as the game.
+///
+/// `rustcraft map <file.png>` likewise saves a top-down map of the world, taking
+/// `--region` and the world flags.
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("export") => return export_world(&args),
+        Some("map") => return map_world(&args),
+        _ => {}
+    }
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -77,30 +162,276 @@ fn main() {
             ..default()
         }))
         .add_plugins(events::EventsPlugin::new().add_plugin(LogPlugin))
-        .add_plugins(world::WorldPlugin)
+        .add_plugins(world_plugin())
+        .insert_resource(world_time())
+        .insert_resource(block_atlas())
         .add_plugins(render::RenderPlugin)
+        .insert_resource(mesh_settings())
+        .insert_resource(fog_settings())
         .add_plugins(player::PlayerPlugin)
         .add_plugins(inventory::InventoryPlugin)
         .add_plugins(interaction::InteractionPlugin)
         .add_plugins(ui::UiPlugin)
         .add_plugins(dropped_item::DroppedItemPlugin)
+        .add_plugins(effects::EffectsPlugin)
+        .add_plugins(map::MapPlugin)
         .add_plugins(avatar::AvatarPlugin)
-        .add_systems(Startup, setup_lighting)
         .run();
 }
 
-fn setup_lighting(mut commands: Commands) {
-    commands.spawn((
-        DirectionalLight {
-            illuminance: 15000.0,
-            shadows_enabled: true,
-            ..default()
-        },
-        Transform::from_rotation(Quat::from_euler(EulerRot::XYZ, -0.9, 0.3, 0.0)),
-    ));
-
-    commands.insert_resource(AmbientLight {
-        color: Color::WHITE,
-        brightness: 200.0,
-    });
+/// Picks the world generator from the command line:
+/// - `--flat [preset]` builds a superflat world,
+/// - `--heightmap <png>` imports terrain from a grayscale image, optionally with
+///   `--materials <png>`, `--height-scale <blocks>`, `--origin <x>,<z>` and `--tile`,
+/// - otherwise the default Perlin terrain is used, with villages, ruins and dungeons.
+///
+/// `--world <dir>` keeps the world's saved state (its time of day) in `dir`.
+fn world_plugin() -> world::WorldPlugin {
+    let args: Vec<String> = std::env::args().collect();
+    let plugin = world::WorldPlugin::new().with_world_gen(world_gen(&args));
+    match arg_value(&args, "--world") {
+        Some(dir) => plugin.with_save_dir(dir),
+        None => plugin,
+    }
+}
+
+fn world_gen(args: &[String]) -> world::generation::WorldGen {
+    let seed = world::generation::DEFAULT_SEED;
+
+    if args.iter().any(|arg| arg == "--flat") {
+        let preset = arg_value(args, "--flat").unwrap_or(world::flat::DEFAULT_PRESET);
+        return match world::flat::FlatGenerator::from_preset(preset) {
+            Ok(generator) => world::generation::WorldGen::new(generator, seed),
+            Err(err) => {
+                exit_with_error(&format!("Invalid flat world preset {:?}: {}", preset, err))
+            }
+        };
+    }
+
+    if let Some(path) = arg_value(args, "--heightmap") {
+        return match heightmap_generator(args, path) {
+            Ok(generator) => world::generation::WorldGen::new(generator, seed),
+            Err(err) => exit_with_error(&format!("Invalid heightmap {:?}: {}", path, err)),
+        };
+    }
+
+    world::generation::WorldGen::new(
+        world::structure::WithStructures(world::generation::PerlinGenerator),
+        seed,
+    )
+}
+
+fn heightmap_generator(
+    args: &[String],
+    path: &str,
+) -> Result<world::heightmap::HeightmapGenerator, String> {
+    let mut generator =
+        world::heightmap::HeightmapGenerator::open(path).map_err(|err| err.to_string())?;
+
+    if let Some(materials) = arg_value(args, "--materials") {
+        generator = generator
+            .with_material_map(materials)
+            .map_err(|err| err.to_string())?;
+    }
+    if let Some(scale) = arg_value(args, "--height-scale") {
+        let scale = scale
+            .parse()
+            .map_err(|_| format!("--height-scale {:?} is not a number", scale))?;
+        generator = generator.with_vertical_scale(scale);
+    }
+    if let Some(origin) = arg_value(args, "--origin") {
+        let parsed = origin
+            .split_once(',')
+            .and_then(|(x, z)| Some(IVec2::new(x.trim().parse().ok()?, z.trim().parse().ok()?)));
+        let origin = parsed.ok_or_else(|| format!("--origin {:?} is not <x>,<z>", origin))?;
+        generator = generator.with_origin(origin);
+    }
+    if args.iter().any(|arg| arg == "--tile") {
+        generator = generator.with_edge_mode(world::heightmap::EdgeMode::Tile);
+    }
+
+    Ok(generator)
+}
+
+/// `--time-rate <factor>` speeds up (or with 0, stops) the day/night cycle.
+fn world_time() -> world::time::WorldTime {
+    let args: Vec<String> = std::env::args().collect();
+    let rate = match arg_value(&args, "--time-rate").map(str::parse) {
+        None => 1.0,
+        Some(Ok(rate)) if rate >= 0.0 => rate,
+        Some(_) => exit_with_error("--time-rate expects a number of at least 0"),
+    };
+    world::time::WorldTime { rate, ..default() }
+}
+
+/// `--mesher naive` switches back to one quad per block face, e.g. to compare
+/// against the default greedy mesher, `--no-ao` starts with ambient occlusion off
+/// (F4 toggles it in game) and `--no-lod` meshes every chunk at full detail.
+fn mesh_settings() -> render::mesh::MeshSettings {
+    let args: Vec<String> = std::env::args().collect();
+    let mesher = match arg_value(&args, "--mesher") {
+        None | Some("greedy") => render::mesh::Mesher::Greedy,
+        Some("naive") => render::mesh::Mesher::Naive,
+        Some(other) => exit_with_error(&format!(
+            "Unknown mesher {:?} (expected \"greedy\" or \"naive\")",
+            other
+        )),
+    };
+    render::mesh::MeshSettings {
+        mesher,
+        ambient_occlusion: !args.iter().any(|arg| arg == "--no-ao"),
+        lod: !args.iter().any(|arg| arg == "--no-lod"),
+    }
+}
+
+/// `--view-distance <chunks>` sets how far terrain fades into the sky and
+/// `--fog-density <factor>` how thick the fog is, 0 turning it off.
+fn fog_settings() -> render::fog::FogSettings {
+    let args: Vec<String> = std::env::args().collect();
+    let mut settings = render::fog::FogSettings::default();
+    if let Some(chunks) = arg_value(&args, "--view-distance") {
+        match chunks.parse::<f32>() {
+            Ok(chunks) if chunks > 0.0 => {
+                settings.view_distance = chunks * world::chunk::CHUNK_SIZE as f32
+            }
+            _ => exit_with_error("--view-distance expects a number of chunks above 0"),
+        }
+    }
+    if let Some(density) = arg_value(&args, "--fog-density") {
+        match density.parse::<f32>() {
+            Ok(density) if density >= 0.0 => settings.density = density,
+            _ => exit_with_error("--fog-density expects a number of at least 0"),
+        }
+    }
+    settings
+}
+
+/// Builds the block texture atlas from `--textures <dir>`, or the images in
+/// `assets/textures/blocks` by default. `--no-textures` uses flat block colors.
+fn block_atlas() -> render::atlas::BlockAtlas {
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--no-textures") {
+        return render::atlas::BlockAtlas::from_colors();
+    }
+    match arg_value(&args, "--textures") {
+        Some(dir) => render::atlas::BlockAtlas::load(dir),
+        None => render::atlas::BlockAtlas::load(render::atlas::default_texture_dir()),
+    }
+}
+
+fn export_world(args: &[String]) {
+    let Some(path) = args.get(2).filter(|path| !path.starts_with("--")) else {
+        exit_with_error(
+            "Usage: rustcraft export <file.glb|file.obj> [--region <x0>,<z0>,<x1>,<z1>]",
+        );
+    };
+    let (min, max) = region(args);
+    let coloring = if args.iter().any(|arg| arg == "--vertex-colors") {
+        export::Coloring::VertexColors
+    } else {
+        export::Coloring::Textured
+    };
+
+    let chunk_map = generate_region(args, min, max);
+    let atlas = block_atlas();
+    let meshes = export::build_region(&chunk_map, min, max, &atlas, coloring);
+    let path = std::path::Path::new(path);
+    match export::write(path, &meshes, &atlas, coloring) {
+        Ok(()) => println!(
+            "Exported chunks ({}, {}) to ({}, {}) to {}",
+            min.0,
+            min.1,
+            max.0,
+            max.1,
+            path.display()
+        ),
+        Err(err) => exit_with_error(&format!("Could not export to {:?}: {}", path, err)),
+    }
+}
+
+fn map_world(args: &[String]) {
+    let Some(path) = args.get(2).filter(|path| !path.starts_with("--")) else {
+        exit_with_error("Usage: rustcraft map <file.png> [--region <x0>,<z0>,<x1>,<z1>]");
+    };
+    let (min, max) = region(args);
+
+    let chunk_map = generate_region(args, min, max);
+    let path = std::path::Path::new(path);
+    match map::save_map(&chunk_map, min, max, path) {
+        Ok(()) => println!(
+            "Saved a map of chunks ({}, {}) to ({}, {}) to {}",
+            min.0,
+            min.1,
+            max.0,
+            max.1,
+            path.display()
+        ),
+        Err(err) => exit_with_error(&format!("Could not save map to {:?}: {}", path, err)),
+    }
+}
+
+/// Most chunks `--region` may cover, so a typo can't ask for a region that would
+/// take hours to generate.
+const MAX_REGION_CHUNKS: i64 = 64 * 64;
+
+/// The chunks picked with `--region <x0>,<z0>,<x1>,<z1>`, or those the game
+/// generates at startup.
+fn region(args: &[String]) -> (world::chunk::ChunkPos, world::chunk::ChunkPos) {
+    let Some(region) = arg_value(args, "--region") else {
+        let last = world::generation::WORLD_CHUNKS - 1;
+        return (
+            world::chunk::ChunkPos(0, 0),
+            world::chunk::ChunkPos(last, last),
+        );
+    };
+    let bounds = region
+        .split(',')
+        .map(|n| n.trim().parse())
+        .collect::<Result<Vec<i32>, _>>();
+    let (min, max) = match bounds.as_deref() {
+        Ok(&[x0, z0, x1, z1]) => (
+            world::chunk::ChunkPos(x0.min(x1), z0.min(z1)),
+            world::chunk::ChunkPos(x0.max(x1), z0.max(z1)),
+        ),
+        _ => exit_with_error(&format!("--region {:?} is not <x0>,<z0>,<x1>,<z1>", region)),
+    };
+    let chunks = (max.0 as i64 - min.0 as i64 + 1) * (max.1 as i64 - min.1 as i64 + 1);
+    if chunks > MAX_REGION_CHUNKS {
+        exit_with_error(&format!(
+            "--region {:?} covers {} chunks, more than the {} allowed",
+            region, chunks, MAX_REGION_CHUNKS
+        ));
+    }
+    (min, max)
+}
+
+/// Generates the chunks from `min` to `max` with the world picked on the command
+/// line, without running the game.
+fn generate_region(
+    args: &[String],
+    min: world::chunk::ChunkPos,
+    max: world::chunk::ChunkPos,
+) -> world::chunk::ChunkMap {
+    let world_gen = world_gen(args);
+    let mut pipeline = world::pipeline::GenerationPipeline::default();
+    let mut chunk_map = world::chunk::ChunkMap::default();
+    for cx in min.0..=max.0 {
+        for cz in min.1..=max.1 {
+            pipeline.generate(world::chunk::ChunkPos(cx, cz), &world_gen, &mut chunk_map);
+        }
+    }
+    chunk_map
+}
+
+/// The value following `flag`, unless it is missing or another flag.
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let i = args.iter().position(|arg| arg == flag)?;
+    args.get(i + 1)
+        .map(String::as_str)
+        .filter(|value| !value.starts_with("--"))
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2);
 }
@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+use image::{ImageResult, Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::world::WorldDir;
+use crate::world::block::BlockType;
+use crate::world::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, ChunkMap, ChunkPos};
+
+/// Brightness of the lowest and the highest possible surface.
+const DEPTH_SHADE: f32 = 0.7;
+const PEAK_SHADE: f32 = 1.15;
+/// How much a column lights up (or darkens) per block it rises above (or sinks
+/// below) its neighbor towards the light, and the most it can.
+const RELIEF_STEP: f32 = 0.08;
+const MAX_RELIEF: f32 = 0.16;
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, save_map_on_keypress);
+    }
+}
+
+/// The top of one column of the world as seen from above.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapColumn {
+    /// Height of the highest non-air block.
+    pub height: i32,
+    /// Its linear color, with translucent blocks (like water) laid over the blocks
+    /// under them.
+    pub color: LinearRgba,
+}
+
+/// The column at `(x, z)`, or `None` if it isn't loaded or holds only air.
+pub fn map_column(chunk_map: &ChunkMap, x: i32, z: i32) -> Option<MapColumn> {
+    let pos = ChunkPos(
+        x.div_euclid(CHUNK_SIZE as i32),
+        z.div_euclid(CHUNK_SIZE as i32),
+    );
+    if !chunk_map.chunks.contains_key(&pos) {
+        return None;
+    }
+
+    let height =
+        (0..CHUNK_HEIGHT as i32).rfind(|&y| chunk_map.get_block(x, y, z) != BlockType::Air)?;
+    // Blend down through translucent blocks until an opaque one shows through
+    let mut color = LinearRgba::NONE;
+    let mut coverage = 0.0;
+    for y in (0..=height).rev() {
+        let block = chunk_map.get_block(x, y, z);
+        if block == BlockType::Air {
+            continue;
+        }
+        let block_color = block.color().to_linear();
+        let alpha = block_color.alpha * (1.0 - coverage);
+        color += block_color.with_alpha(1.0) * alpha;
+        coverage += alpha;
+        if coverage > 0.99 {
+            break;
+        }
+    }
+    if coverage == 0.0 {
+        return None;
+    }
+    Some(MapColumn {
+        height,
+        color: (color / coverage).with_alpha(1.0),
+    })
+}
+
+/// The pixel for `column`: brighter the higher it is, and lit from one side so
+/// slopes stand out. `toward_light` is the next column on that side.
+pub fn shade(column: MapColumn, toward_light: Option<MapColumn>) -> Rgba<u8> {
+    let t = column.height as f32 / (CHUNK_HEIGHT - 1) as f32;
+    let mut brightness = DEPTH_SHADE + (PEAK_SHADE - DEPTH_SHADE) * t;
+    if let Some(neighbor) = toward_light {
+        brightness +=
+            ((column.height - neighbor.height) as f32 * RELIEF_STEP).clamp(-MAX_RELIEF, MAX_RELIEF);
+    }
+    let color = (column.color * brightness).with_alpha(1.0);
+    Rgba(Color::from(color).to_srgba().to_u8_array())
+}
+
+/// Renders the chunks from `min` to `max` (inclusive) from above, one pixel per
+/// block column. Like the minimap, north (+Z) is up and +X to the left, with the
+/// light coming from the north; columns that aren't loaded are left transparent.
+pub fn render_map(chunk_map: &ChunkMap, min: ChunkPos, max: ChunkPos) -> RgbaImage {
+    // The world position of the top left pixel
+    let corner = (IVec2::new(max.0, max.1) + 1) * CHUNK_SIZE as i32 - 1;
+    let width = (max.0 - min.0 + 1) as u32 * CHUNK_SIZE as u32;
+    let height = (max.1 - min.1 + 1) as u32 * CHUNK_SIZE as u32;
+
+    let mut image = RgbaImage::new(width, height);
+    for px in 0..width {
+        let x = corner.x - px as i32;
+        // Walk down each column of pixels so the neighbor above is at hand
+        let mut above = map_column(chunk_map, x, corner.y + 1);
+        for pz in 0..height {
+            let z = corner.y - pz as i32;
+            let column = map_column(chunk_map, x, z);
+            if let Some(column) = column {
+                image.put_pixel(px, pz, shade(column, above));
+            }
+            above = column;
+        }
+    }
+    image
+}
+
+/// The smallest and largest loaded chunk positions, or `None` if nothing is loaded.
+pub fn loaded_bounds(chunk_map: &ChunkMap) -> Option<(ChunkPos, ChunkPos)> {
+    let mut positions = chunk_map.chunks.keys();
+    let first = *positions.next()?;
+    Some(positions.fold((first, first), |(min, max), pos| {
+        (
+            ChunkPos(min.0.min(pos.0), min.1.min(pos.1)),
+            ChunkPos(max.0.max(pos.0), max.1.max(pos.1)),
+        )
+    }))
+}
+
+/// Renders the chunks from `min` to `max` and saves them as a PNG at `path`.
+pub fn save_map(
+    chunk_map: &ChunkMap,
+    min: ChunkPos,
+    max: ChunkPos,
+    path: &Path,
+) -> ImageResult<()> {
+    render_map(chunk_map, min, max).save(path)
+}
+
+/// F2 saves a map of every loaded chunk into the world directory, or the working
+/// directory if the world isn't saved.
+fn save_map_on_keypress(
+    keys: Res<ButtonInput<KeyCode>>,
+    chunk_map: Res<ChunkMap>,
+    world_dir: Option<Res<WorldDir>>,
+) {
+    if !keys.just_pressed(KeyCode::F2) {
+        return;
+    }
+    let Some((min, max)) = loaded_bounds(&chunk_map) else {
+        return;
+    };
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    let name = format!("map-{}.png", seconds);
+    let path = match world_dir {
+        Some(dir) => dir.0.join(name),
+        None => PathBuf::from(name),
+    };
+    match save_map(&chunk_map, min, max, &path) {
+        Ok(()) => info!("Saved map to {}", path.display()),
+        Err(err) => warn!("Could not save map to {}: {}", path.display(), err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::chunk::Chunk;
+
+    #[test]
+    fn higher_ground_is_brighter_and_water_shows_its_floor() {
+        let mut chunk_map = ChunkMap::default();
+        chunk_map.chunks.insert(ChunkPos(0, 0), Chunk::new());
+        for x in 0..CHUNK_SIZE as i32 {
+            for z in 0..CHUNK_SIZE as i32 {
+                chunk_map.set_block(x, 10, z, BlockType::Grass);
+            }
+        }
+        // A hill, a pond over sand and a hole down to nothing
+        chunk_map.set_block(2, 20, 2, BlockType::Grass);
+        chunk_map.set_block(8, 10, 8, BlockType::Sand);
+        chunk_map.set_block(8, 11, 8, BlockType::Water);
+        chunk_map.set_block(12, 10, 12, BlockType::Air);
+
+        let image = render_map(&chunk_map, ChunkPos(0, 0), ChunkPos(1, 0));
+        assert_eq!(image.dimensions(), (32, 16));
+
+        // North up and +X to the left, so pixels count back from (31, 15)
+        let pixel = |x: u32, z: u32| image.get_pixel(31 - x, 15 - z).0;
+        let flat = pixel(5, 5);
+        let hill = pixel(2, 2);
+        let behind_hill = pixel(2, 1);
+        assert!(hill[1] > flat[1]);
+        assert!(behind_hill[1] < flat[1]);
+
+        // Blue water tinted by the sand below
+        let pond = pixel(8, 8);
+        assert!(pond[2] > pond[1] && pond[0] > flat[0]);
+
+        // Nothing at all, and nothing loaded
+        assert_eq!(pixel(12, 12)[3], 0);
+        assert_eq!(pixel(20, 5)[3], 0);
+
+        assert_eq!(
+            loaded_bounds(&chunk_map),
+            Some((ChunkPos(0, 0), ChunkPos(0, 0)))
+        );
+    }
+}
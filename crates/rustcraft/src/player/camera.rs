@@ -3,8 +3,13 @@ use bevy::prelude::*;
 use bevy::window::CursorGrabMode;
 
 use crate::avatar::CameraMode;
-use crate::events::{GameModeChangedEvent, PlayerMovedEvent};
+use crate::events::{
+    GameModeChangedEvent, PlayerMovedEvent, SneakToggledEvent, SprintToggledEvent,
+};
+use crate::player::health::{DamageCause, Health, fall_damage};
+use crate::player::hunger::{Hunger, JUMP_EXHAUSTION, SPRINT_JUMP_EXHAUSTION};
 use crate::world::chunk::ChunkMap;
+use crate::world::model::{ModelBox, collision_boxes, touches_fluid};
 
 #[derive(Component)]
 pub struct FlyCam;
@@ -23,9 +28,22 @@ pub struct Player {
     pub position: Vec3,
     pub velocity_y: f32,
     pub grounded: bool,
+    /// Whether any part of the body is in water (or another fluid).
+    pub in_fluid: bool,
+    pub sprinting: bool,
+    pub sneaking: bool,
 }
 
 impl Player {
+    /// Height of the eyes above the feet, lower while sneaking.
+    pub fn eye_height(&self) -> f32 {
+        if self.sneaking {
+            SNEAK_EYE_HEIGHT
+        } else {
+            EYE_HEIGHT
+        }
+    }
+
     pub fn location(&self, transform: &Transform) -> Location {
         let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
         Location {
@@ -42,6 +60,16 @@ impl Player {
 pub struct CameraSettings {
     pub sensitivity: f32,
     pub speed: f32,
+    /// Factors `speed` is multiplied by while sprinting and while sneaking.
+    pub sprint_multiplier: f32,
+    pub sneak_multiplier: f32,
+    /// Factor `speed` is multiplied by while in a fluid.
+    pub swim_multiplier: f32,
+    /// Tallest rise (like a slab) walked straight up onto in survival.
+    pub step_height: f32,
+    /// Whether walking into a rise too tall to step but low enough to jump onto
+    /// jumps by itself.
+    pub auto_jump: bool,
 }
 
 impl Default for CameraSettings {
@@ -49,6 +77,11 @@ impl Default for CameraSettings {
         Self {
             sensitivity: 0.003,
             speed: 12.0,
+            sprint_multiplier: 1.5,
+            sneak_multiplier: 0.3,
+            swim_multiplier: 0.5,
+            step_height: 0.6,
+            auto_jump: true,
         }
     }
 }
@@ -71,17 +104,36 @@ pub enum GameState {
     Playing,
     Paused,
     InInventory,
+    /// Waiting on the death screen for the player to respawn.
+    Dead,
 }
 
 const PLAYER_HALF_WIDTH: f32 = 0.3;
 const PLAYER_HEIGHT: f32 = 1.8;
 pub const EYE_HEIGHT: f32 = 1.7;
+/// Where the player's eyes start out, and where they come back after dying.
+const SPAWN_EYE_POSITION: Vec3 = Vec3::new(64.0, 40.0, 64.0);
+const SNEAK_EYE_HEIGHT: f32 = 1.45;
 const GRAVITY: f32 = 32.0;
 const JUMP_VELOCITY: f32 = 9.0;
 const TERMINAL_VELOCITY: f32 = 78.4;
+/// Upward push of a fluid, just short of cancelling gravity so the player sinks
+/// slowly, and how quickly it slows vertical movement down.
+const BUOYANCY: f32 = 26.0;
+const FLUID_DRAG: f32 = 4.0;
+/// Upward acceleration while holding Space in a fluid.
+const SWIM_ACCELERATION: f32 = 16.0;
+/// Tallest rise auto-jump tries for, within reach of a jump.
+const AUTO_JUMP_HEIGHT: f32 = 1.0;
+/// Longest time between two presses of W that starts a sprint, in seconds.
+const DOUBLE_TAP_WINDOW: f32 = 0.3;
+/// How much wider the field of view gets while sprinting, and how quickly it
+/// eases there and back.
+const SPRINT_FOV_WIDENING: f32 = 0.15;
+const FOV_EASE_SPEED: f32 = 10.0;
 
 pub fn spawn_camera(mut commands: Commands) {
-    let eye_pos = Vec3::new(64.0, 40.0, 64.0);
+    let eye_pos = SPAWN_EYE_POSITION;
     let feet_pos = eye_pos - Vec3::new(0.0, EYE_HEIGHT, 0.0);
 
     commands.spawn((
@@ -92,10 +144,28 @@ pub fn spawn_camera(mut commands: Commands) {
             position: feet_pos,
             velocity_y: 0.0,
             grounded: false,
+            in_fluid: false,
+            sprinting: false,
+            sneaking: false,
         },
+        Health::default(),
+        Hunger::default(),
     ));
 }
 
+/// Puts the player back at the spawn point, at rest and looking the way they
+/// first did.
+pub fn respawn(transform: &mut Transform, player: &mut Player) {
+    *transform = Transform::from_translation(SPAWN_EYE_POSITION)
+        .looking_at(Vec3::new(64.0, 20.0, 0.0), Vec3::Y);
+    player.position = SPAWN_EYE_POSITION - Vec3::new(0.0, EYE_HEIGHT, 0.0);
+    player.velocity_y = 0.0;
+    player.grounded = false;
+    player.in_fluid = false;
+    player.sprinting = false;
+    player.sneaking = false;
+}
+
 pub fn initial_cursor_grab(mut windows: Query<&mut Window>) {
     if let Ok(mut window) = windows.get_single_mut() {
         window.cursor_options.grab_mode = CursorGrabMode::Locked;
@@ -133,82 +203,225 @@ pub fn camera_look(
     }
 }
 
-fn collides_with_world(pos: Vec3, chunk_map: &ChunkMap) -> bool {
-    let min_x = (pos.x - PLAYER_HALF_WIDTH).floor() as i32;
-    let max_x = (pos.x + PLAYER_HALF_WIDTH - 0.001).floor() as i32;
-    let min_y = pos.y.floor() as i32;
-    let max_y = (pos.y + PLAYER_HEIGHT - 0.001).floor() as i32;
-    let min_z = (pos.z - PLAYER_HALF_WIDTH).floor() as i32;
-    let max_z = (pos.z + PLAYER_HALF_WIDTH - 0.001).floor() as i32;
-
-    for bx in min_x..=max_x {
-        for by in min_y..=max_y {
-            for bz in min_z..=max_z {
-                if chunk_map.get_block(bx, by, bz).is_solid() {
-                    return true;
-                }
-            }
-        }
-    }
-    false
+/// How far the player's box is shrunk for collision, so a player resting against a
+/// block doesn't count as inside it after rounding.
+const COLLISION_SKIN: f32 = 0.001;
+
+/// The player's bounding box with the feet at `pos`.
+fn player_box(pos: Vec3) -> ModelBox {
+    ModelBox::new(
+        pos + Vec3::new(-PLAYER_HALF_WIDTH, 0.0, -PLAYER_HALF_WIDTH),
+        pos + Vec3::new(PLAYER_HALF_WIDTH, PLAYER_HEIGHT, PLAYER_HALF_WIDTH),
+    )
+}
+
+fn collision_area(pos: Vec3) -> ModelBox {
+    let player = player_box(pos);
+    ModelBox::new(
+        player.min + Vec3::splat(COLLISION_SKIN),
+        player.max - Vec3::splat(COLLISION_SKIN),
+    )
 }
 
 fn is_on_ground(pos: Vec3, chunk_map: &ChunkMap) -> bool {
-    let check_pos = Vec3::new(pos.x, pos.y - 0.001, pos.z);
-    let min_x = (check_pos.x - PLAYER_HALF_WIDTH).floor() as i32;
-    let max_x = (check_pos.x + PLAYER_HALF_WIDTH - 0.001).floor() as i32;
-    let min_z = (check_pos.z - PLAYER_HALF_WIDTH).floor() as i32;
-    let max_z = (check_pos.z + PLAYER_HALF_WIDTH - 0.001).floor() as i32;
-    let by = (check_pos.y).floor() as i32;
-
-    for bx in min_x..=max_x {
-        for bz in min_z..=max_z {
-            if chunk_map.get_block(bx, by, bz).is_solid() {
-                return true;
-            }
-        }
+    let player = collision_area(pos);
+    let feet = ModelBox::new(
+        Vec3::new(player.min.x, pos.y - 0.01, player.min.z),
+        Vec3::new(player.max.x, pos.y, player.max.z),
+    );
+    !collision_boxes(feet, chunk_map).is_empty()
+}
+
+/// Moves `pos` by `delta` along `axis`, stopping flush against the nearest block
+/// box in the way. Returns whether it was stopped.
+fn move_axis(pos: &mut Vec3, axis: usize, delta: f32, chunk_map: &ChunkMap) -> bool {
+    pos[axis] += delta;
+    let hits = collision_boxes(collision_area(*pos), chunk_map);
+    if hits.is_empty() {
+        return false;
+    }
+
+    let player = player_box(*pos);
+    if delta > 0.0 {
+        let wall = hits
+            .iter()
+            .map(|b| b.min[axis])
+            .fold(f32::INFINITY, f32::min);
+        pos[axis] += wall - player.max[axis];
+    } else {
+        let wall = hits
+            .iter()
+            .map(|b| b.max[axis])
+            .fold(f32::NEG_INFINITY, f32::max);
+        pos[axis] += wall - player.min[axis];
     }
-    false
+    true
 }
 
-fn move_with_collision(current_pos: Vec3, delta: Vec3, chunk_map: &ChunkMap) -> (Vec3, bool, bool) {
+/// Cancels the parts of the horizontal `delta` that would carry a player standing
+/// at `pos` off the blocks under them.
+fn keep_on_edges(pos: Vec3, delta: Vec3, chunk_map: &ChunkMap) -> Vec3 {
+    let mut delta = delta;
+    if !is_on_ground(pos + Vec3::new(delta.x, 0.0, 0.0), chunk_map) {
+        delta.x = 0.0;
+    }
+    if !is_on_ground(pos + Vec3::new(delta.x, 0.0, delta.z), chunk_map) {
+        delta.z = 0.0;
+    }
+    delta
+}
+
+/// Where a move ended up and what it ran into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Moved {
+    position: Vec3,
+    hit_floor: bool,
+    hit_ceiling: bool,
+    /// Walked into a rise too tall to step onto but low enough to jump onto.
+    step_blocked: bool,
+}
+
+/// Moves the horizontal part of `delta` from `start` with the player raised by up
+/// to `height`, then lowers them back onto whatever they walked onto. Returns the
+/// position if that gets further than `reached` did.
+fn step_up(
+    start: Vec3,
+    delta: Vec3,
+    height: f32,
+    reached: Vec3,
+    chunk_map: &ChunkMap,
+) -> Option<Vec3> {
+    let mut pos = start;
+    move_axis(&mut pos, 1, height, chunk_map);
+    let raised = pos.y - start.y;
+    move_axis(&mut pos, 0, delta.x, chunk_map);
+    move_axis(&mut pos, 2, delta.z, chunk_map);
+    move_axis(&mut pos, 1, -raised, chunk_map);
+
+    let gained = (pos - start).xz().length() - (reached - start).xz().length();
+    (gained > 1e-4).then_some(pos)
+}
+
+/// Moves the player at `current_pos` by `delta`, sliding along whatever blocks are
+/// in the way. A grounded player (with `step_height` above zero) automatically
+/// climbs obstacles no taller than `step_height`.
+fn move_with_collision(
+    current_pos: Vec3,
+    delta: Vec3,
+    step_height: f32,
+    chunk_map: &ChunkMap,
+) -> Moved {
     let mut pos = current_pos;
-    let mut hit_floor = false;
-    let mut hit_ceiling = false;
-
-    // X axis
-    pos.x += delta.x;
-    if collides_with_world(pos, chunk_map) {
-        if delta.x > 0.0 {
-            pos.x = (pos.x + PLAYER_HALF_WIDTH).floor() - PLAYER_HALF_WIDTH;
-        } else {
-            pos.x = (pos.x - PLAYER_HALF_WIDTH).floor() + 1.0 + PLAYER_HALF_WIDTH;
+
+    move_axis(&mut pos, 0, delta.x, chunk_map);
+    let hit_y = move_axis(&mut pos, 1, delta.y, chunk_map);
+    move_axis(&mut pos, 2, delta.z, chunk_map);
+
+    let mut moved = Moved {
+        position: pos,
+        hit_floor: hit_y && delta.y <= 0.0,
+        hit_ceiling: hit_y && delta.y > 0.0,
+        step_blocked: false,
+    };
+    let blocked = pos.x != current_pos.x + delta.x || pos.z != current_pos.z + delta.z;
+    if step_height <= 0.0 || !blocked {
+        return moved;
+    }
+
+    if let Some(stepped) = step_up(current_pos, delta, step_height, pos, chunk_map) {
+        moved.position = stepped;
+        moved.hit_floor = true;
+        moved.hit_ceiling = false;
+    } else {
+        moved.step_blocked =
+            step_up(current_pos, delta, AUTO_JUMP_HEIGHT, pos, chunk_map).is_some();
+    }
+    moved
+}
+
+/// In Survival, holding Shift sneaks.
+pub fn update_sneak(
+    game_state: Res<GameState>,
+    keys: Res<ButtonInput<KeyCode>>,
+    game_mode: Res<GameMode>,
+    mut ev_sneak: EventWriter<SneakToggledEvent>,
+    mut query: Query<(&Transform, &mut Player), With<FlyCam>>,
+) {
+    // Keys don't count outside of play, so leaving it stops sneaking
+    let sneaking = *game_state == GameState::Playing
+        && *game_mode == GameMode::Survival
+        && keys.pressed(KeyCode::ShiftLeft);
+    for (transform, mut player) in &mut query {
+        if sneaking != player.sneaking {
+            player.sneaking = sneaking;
+            ev_sneak.send(SneakToggledEvent {
+                sneaking,
+                player: player.location(transform),
+            });
         }
     }
+}
 
-    // Y axis
-    pos.y += delta.y;
-    if collides_with_world(pos, chunk_map) {
-        if delta.y > 0.0 {
-            pos.y = (pos.y + PLAYER_HEIGHT).floor() - PLAYER_HEIGHT;
-            hit_ceiling = true;
-        } else {
-            pos.y = pos.y.floor() + 1.0;
-            hit_floor = true;
+/// Ctrl or a double tap of W sprints until W is let go or the player sneaks.
+pub fn update_sprint(
+    game_state: Res<GameState>,
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut last_forward_press: Local<Option<f32>>,
+    mut ev_sprint: EventWriter<SprintToggledEvent>,
+    mut query: Query<(&Transform, &mut Player), With<FlyCam>>,
+) {
+    let playing = *game_state == GameState::Playing;
+    let now = time.elapsed_secs();
+    let double_tap = playing
+        && keys.just_pressed(KeyCode::KeyW)
+        && last_forward_press.is_some_and(|last| now - last <= DOUBLE_TAP_WINDOW);
+    if playing && keys.just_pressed(KeyCode::KeyW) {
+        *last_forward_press = Some(now);
+    }
+    let forward = playing && keys.pressed(KeyCode::KeyW);
+    let sprint_key = playing && keys.pressed(KeyCode::ControlLeft);
+
+    for (transform, mut player) in &mut query {
+        let sprinting =
+            forward && !player.sneaking && (player.sprinting || double_tap || sprint_key);
+        if sprinting != player.sprinting {
+            player.sprinting = sprinting;
+            ev_sprint.send(SprintToggledEvent {
+                sprinting,
+                player: player.location(transform),
+            });
         }
     }
+}
 
-    // Z axis
-    pos.z += delta.z;
-    if collides_with_world(pos, chunk_map) {
-        if delta.z > 0.0 {
-            pos.z = (pos.z + PLAYER_HALF_WIDTH).floor() - PLAYER_HALF_WIDTH;
+/// Widens the field of view while sprinting.
+pub fn update_sprint_fov(
+    time: Res<Time>,
+    mut query: Query<(&Player, &mut Projection), With<FlyCam>>,
+) {
+    let base = PerspectiveProjection::default().fov;
+    for (player, mut projection) in &mut query {
+        let Projection::Perspective(perspective) = &*projection else {
+            continue;
+        };
+        let target = if player.sprinting {
+            base * (1.0 + SPRINT_FOV_WIDENING)
         } else {
-            pos.z = (pos.z - PLAYER_HALF_WIDTH).floor() + 1.0 + PLAYER_HALF_WIDTH;
+            base
+        };
+        if perspective.fov == target {
+            continue;
+        }
+        let step = (FOV_EASE_SPEED * time.delta_secs()).min(1.0);
+        let mut fov = perspective.fov + (target - perspective.fov) * step;
+        if (fov - target).abs() < 1e-3 {
+            fov = target;
+        }
+        if let Projection::Perspective(perspective) = &mut *projection {
+            perspective.fov = fov;
         }
     }
-
-    (pos, hit_floor, hit_ceiling)
 }
 
 pub fn camera_movement(
@@ -219,7 +432,7 @@ pub fn camera_movement(
     chunk_map: Res<ChunkMap>,
     game_mode: Res<GameMode>,
     mut ev_moved: EventWriter<PlayerMovedEvent>,
-    mut query: Query<(&mut Transform, &mut Player), With<FlyCam>>,
+    mut query: Query<(&mut Transform, &mut Player, &mut Health, &mut Hunger), With<FlyCam>>,
 ) {
     if *game_state != GameState::Playing {
         return;
@@ -227,9 +440,22 @@ pub fn camera_movement(
 
     let dt = time.delta_secs();
 
-    for (mut transform, mut player) in &mut query {
+    for (mut transform, mut player, mut health, mut hunger) in &mut query {
         let forward = transform.forward().as_vec3();
         let right = transform.right().as_vec3();
+        player.in_fluid = touches_fluid(collision_area(player.position), &chunk_map);
+        let speed = if player.sprinting {
+            settings.speed * settings.sprint_multiplier
+        } else if player.sneaking {
+            settings.speed * settings.sneak_multiplier
+        } else {
+            settings.speed
+        };
+        let speed = if player.in_fluid {
+            speed * settings.swim_multiplier
+        } else {
+            speed
+        };
 
         let delta = match *game_mode {
             GameMode::Creative => {
@@ -255,7 +481,7 @@ pub fn camera_movement(
                 if velocity != Vec3::ZERO {
                     velocity = velocity.normalize();
                 }
-                velocity * settings.speed * dt
+                velocity * speed * dt
             }
             GameMode::Survival => {
                 let forward_xz = Vec3::new(forward.x, 0.0, forward.z).normalize_or_zero();
@@ -280,35 +506,76 @@ pub fn camera_movement(
 
                 player.grounded = is_on_ground(player.position, &chunk_map);
 
-                if keys.just_pressed(KeyCode::Space) && player.grounded {
-                    player.velocity_y = JUMP_VELOCITY;
-                    player.grounded = false;
+                if player.in_fluid {
+                    let swim = if keys.pressed(KeyCode::Space) {
+                        SWIM_ACCELERATION
+                    } else {
+                        0.0
+                    };
+                    player.velocity_y +=
+                        (BUOYANCY - GRAVITY + swim - player.velocity_y * FLUID_DRAG) * dt;
+                } else {
+                    if keys.just_pressed(KeyCode::Space) && player.grounded {
+                        player.velocity_y = JUMP_VELOCITY;
+                        player.grounded = false;
+                        hunger.exhaust(if player.sprinting {
+                            SPRINT_JUMP_EXHAUSTION
+                        } else {
+                            JUMP_EXHAUSTION
+                        });
+                    }
+                    player.velocity_y -= GRAVITY * dt;
                 }
-
-                player.velocity_y -= GRAVITY * dt;
                 player.velocity_y = player.velocity_y.max(-TERMINAL_VELOCITY);
 
-                Vec3::new(
-                    horizontal.x * settings.speed * dt,
+                let delta = Vec3::new(
+                    horizontal.x * speed * dt,
                     player.velocity_y * dt,
-                    horizontal.z * settings.speed * dt,
-                )
+                    horizontal.z * speed * dt,
+                );
+                // Sneaking never walks off an edge
+                if player.sneaking && player.grounded {
+                    keep_on_edges(player.position, delta, &chunk_map)
+                } else {
+                    delta
+                }
             }
         };
 
         let old_pos = player.position;
-        let (new_pos, hit_floor, hit_ceiling) =
-            move_with_collision(player.position, delta, &chunk_map);
+        let walking = *game_mode == GameMode::Survival && player.grounded && !player.in_fluid;
+        let step_height = if walking { settings.step_height } else { 0.0 };
+        let moved = move_with_collision(player.position, delta, step_height, &chunk_map);
+        let new_pos = moved.position;
         player.position = new_pos;
 
         if *game_mode == GameMode::Survival {
-            if hit_floor {
+            // Swimming into a wall climbs out onto the ledge above it
+            let blocked = new_pos.x != old_pos.x + delta.x || new_pos.z != old_pos.z + delta.z;
+            if player.in_fluid && blocked && keys.pressed(KeyCode::Space) {
+                player.velocity_y = player.velocity_y.max(JUMP_VELOCITY);
+            }
+            if moved.hit_floor {
+                // Checked again at the new position, as a fast fall can reach the
+                // bottom of shallow water in the frame it enters it
+                let in_fluid =
+                    player.in_fluid || touches_fluid(collision_area(new_pos), &chunk_map);
+                health.hurt(fall_damage(player.velocity_y, in_fluid), DamageCause::Fall);
                 player.velocity_y = 0.0;
                 player.grounded = true;
             }
-            if hit_ceiling {
+            if moved.hit_ceiling {
                 player.velocity_y = 0.0;
             }
+            if moved.step_blocked && settings.auto_jump && !player.sneaking {
+                player.velocity_y = JUMP_VELOCITY;
+                player.grounded = false;
+                hunger.exhaust(if player.sprinting {
+                    SPRINT_JUMP_EXHAUSTION
+                } else {
+                    JUMP_EXHAUSTION
+                });
+            }
         }
 
         if player.position != old_pos {
@@ -318,7 +585,7 @@ pub fn camera_movement(
                 player: player.location(&transform),
             });
         }
-        transform.translation = player.position + Vec3::new(0.0, EYE_HEIGHT, 0.0);
+        transform.translation = player.position + Vec3::new(0.0, player.eye_height(), 0.0);
     }
 }
 
@@ -353,7 +620,7 @@ pub fn pause_on_focus_lost(
     mut focus_events: EventReader<bevy::window::WindowFocused>,
 ) {
     for event in focus_events.read() {
-        if !event.focused && *game_state != GameState::Paused {
+        if !event.focused && !matches!(*game_state, GameState::Paused | GameState::Dead) {
             *game_state = GameState::Paused;
         }
     }
@@ -373,7 +640,7 @@ pub fn enforce_cursor_state(game_state: Res<GameState>, mut windows: Query<&mut
                 window.cursor_options.visible = false;
             }
         }
-        GameState::Paused | GameState::InInventory => {
+        GameState::Paused | GameState::InInventory | GameState::Dead => {
             if window.cursor_options.grab_mode != CursorGrabMode::None {
                 window.cursor_options.grab_mode = CursorGrabMode::None;
                 window.cursor_options.visible = true;
@@ -391,6 +658,7 @@ pub fn toggle_pause(
         let new_state = match *game_state {
             GameState::Playing => GameState::Paused,
             GameState::Paused | GameState::InInventory => GameState::Playing,
+            GameState::Dead => return,
         };
         *game_state = new_state;
 
@@ -400,7 +668,7 @@ pub fn toggle_pause(
                     window.cursor_options.grab_mode = CursorGrabMode::Locked;
                     window.cursor_options.visible = false;
                 }
-                GameState::Paused | GameState::InInventory => {
+                GameState::Paused | GameState::InInventory | GameState::Dead => {
                     window.cursor_options.grab_mode = CursorGrabMode::None;
                     window.cursor_options.visible = true;
                 }
@@ -421,7 +689,7 @@ pub fn toggle_inventory(
     let new_state = match *game_state {
         GameState::Playing => GameState::InInventory,
         GameState::InInventory => GameState::Playing,
-        GameState::Paused => return,
+        GameState::Paused | GameState::Dead => return,
     };
     *game_state = new_state;
 
@@ -435,7 +703,75 @@ pub fn toggle_inventory(
                 window.cursor_options.grab_mode = CursorGrabMode::None;
                 window.cursor_options.visible = true;
             }
-            GameState::Paused => {}
+            GameState::Paused | GameState::Dead => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block::BlockType;
+    use crate::world::chunk::{Chunk, ChunkPos};
+
+    #[test]
+    fn sneaking_stops_at_block_edges() {
+        let mut chunk_map = ChunkMap::default();
+        chunk_map.chunks.insert(ChunkPos(0, 0), Chunk::new());
+        chunk_map.set_block(0, 10, 0, BlockType::Stone);
+        let pos = Vec3::new(0.5, 11.0, 0.5);
+
+        // Hanging over the edge is fine as long as some of the player is above it
+        let delta = Vec3::new(0.7, 0.0, 0.0);
+        assert_eq!(keep_on_edges(pos, delta, &chunk_map), delta);
+        // Stepping past it is not, in either direction
+        let delta = Vec3::new(0.9, 0.0, -0.9);
+        assert_eq!(keep_on_edges(pos, delta, &chunk_map), Vec3::ZERO);
+        // Moving along the edge still works
+        let delta = Vec3::new(0.9, 0.0, 0.2);
+        assert_eq!(
+            keep_on_edges(pos, delta, &chunk_map),
+            Vec3::new(0.0, 0.0, 0.2)
+        );
+    }
+
+    #[test]
+    fn steps_onto_slabs_and_auto_jumps_full_blocks() {
+        let mut chunk_map = ChunkMap::default();
+        chunk_map.chunks.insert(ChunkPos(0, 0), Chunk::new());
+        for x in 0..8 {
+            chunk_map.set_block(x, 9, 0, BlockType::Stone);
         }
+        chunk_map.set_block(3, 10, 0, BlockType::StoneSlab);
+        chunk_map.set_block(6, 10, 0, BlockType::Stone);
+        let delta = Vec3::new(0.2, -0.01, 0.0);
+
+        // Walking into the slab steps up onto it
+        let pos = Vec3::new(2.69, 10.0, 0.5);
+        let moved = move_with_collision(pos, delta, 0.6, &chunk_map);
+        assert_eq!(moved.position.y, 10.5);
+        assert!((moved.position.x - 2.89).abs() < 1e-4);
+        assert!(moved.hit_floor && !moved.step_blocked);
+
+        // Without stepping, or into a full block, the player stops at the wall,
+        // and only a rise within jumping reach asks for an auto-jump
+        let moved = move_with_collision(pos, delta, 0.0, &chunk_map);
+        assert!((moved.position.x - 2.7).abs() < 1e-4);
+        assert!(!moved.step_blocked);
+        let pos = Vec3::new(5.69, 10.0, 0.5);
+        let moved = move_with_collision(pos, delta, 0.6, &chunk_map);
+        assert!((moved.position.x - 5.7).abs() < 1e-4 && moved.position.y == 10.0);
+        assert!(moved.step_blocked);
+
+        chunk_map.set_block(6, 11, 0, BlockType::Stone);
+        let moved = move_with_collision(pos, delta, 0.6, &chunk_map);
+        assert!(!moved.step_blocked);
+
+        // A low ceiling leaves no room to step up under
+        chunk_map.set_block(2, 12, 0, BlockType::Stone);
+        chunk_map.set_block(3, 12, 0, BlockType::Stone);
+        let pos = Vec3::new(2.69, 10.0, 0.5);
+        let moved = move_with_collision(pos, delta, 0.6, &chunk_map);
+        assert_eq!(moved.position.y, 10.0);
     }
 }
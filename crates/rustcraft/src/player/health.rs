@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+
+use crate::events::{PlayerDamagedEvent, PlayerDiedEvent};
+use crate::player::camera::{FlyCam, GameMode, GameState, Player};
+use crate::player::hunger::{Hunger, REGEN_EXHAUSTION, REGEN_MIN_HUNGER};
+use crate::world::chunk::ChunkMap;
+
+/// Health of a fresh player, two points to a heart.
+pub const MAX_HEALTH: f32 = 20.0;
+/// Seconds of breath under water.
+pub const MAX_AIR: f32 = 15.0;
+/// Falls slower than this hurt nothing; every unit of landing speed past it costs
+/// a point, roughly one per block fallen beyond the third.
+const SAFE_LANDING_SPEED: f32 = 14.0;
+const FALL_DAMAGE_PER_SPEED: f32 = 0.5;
+/// Below this height the player is in the void.
+const VOID_DEPTH: f32 = -32.0;
+/// Damage dealt by drowning and the void each time `HAZARD_INTERVAL` passes.
+const DROWNING_DAMAGE: f32 = 2.0;
+const VOID_DAMAGE: f32 = 4.0;
+const HAZARD_INTERVAL: f32 = 1.0;
+/// How much breath comes back per second above water.
+const AIR_REFILL_RATE: f32 = 5.0;
+/// Seconds without damage before health regenerates, and points gained per second
+/// after that.
+const REGEN_DELAY: f32 = 4.0;
+const REGEN_RATE: f32 = 0.5;
+
+/// What hurt the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageCause {
+    Fall,
+    Drowning,
+    Void,
+    Starvation,
+}
+
+#[derive(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    /// Seconds of breath left, drained while the head is under water.
+    pub air: f32,
+    /// Seconds since the player was last hurt.
+    pub since_damage: f32,
+    /// Time spent in a hazard (drowning or the void) since it last hurt.
+    hazard_time: f32,
+    /// Damage dealt since `apply_damage` last ran.
+    pending: Vec<(f32, DamageCause)>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            current: MAX_HEALTH,
+            max: MAX_HEALTH,
+            air: MAX_AIR,
+            since_damage: 0.0,
+            hazard_time: 0.0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Health {
+    /// Queues `amount` of damage, applied (and reported) by `apply_damage`.
+    pub fn hurt(&mut self, amount: f32, cause: DamageCause) {
+        if amount > 0.0 {
+            self.pending.push((amount, cause));
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    /// Back to full health and breath, forgetting any damage not yet applied.
+    pub fn restore(&mut self) {
+        *self = Self {
+            max: self.max,
+            current: self.max,
+            ..default()
+        };
+    }
+}
+
+/// Damage for landing at `velocity_y` (negative when falling). Fluids break the
+/// fall completely.
+pub fn fall_damage(velocity_y: f32, in_fluid: bool) -> f32 {
+    if in_fluid {
+        return 0.0;
+    }
+    ((-velocity_y - SAFE_LANDING_SPEED) * FALL_DAMAGE_PER_SPEED)
+        .max(0.0)
+        .floor()
+}
+
+/// Drowning once the breath runs out, and the void below the world.
+pub fn hazard_damage(
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    time: Res<Time>,
+    chunk_map: Res<ChunkMap>,
+    mut query: Query<(&Player, &mut Health), With<FlyCam>>,
+) {
+    if *game_state == GameState::Paused || *game_state == GameState::Dead {
+        return;
+    }
+    let dt = time.delta_secs();
+
+    for (player, mut health) in &mut query {
+        // The player's own head, not the camera, which orbits it in third person
+        let eye = (player.position + Vec3::Y * player.eye_height())
+            .floor()
+            .as_ivec3();
+        let head_in_fluid = chunk_map.get_block(eye.x, eye.y, eye.z).is_fluid();
+        if head_in_fluid && *game_mode == GameMode::Survival {
+            health.air = (health.air - dt).max(0.0);
+        } else {
+            health.air = (health.air + AIR_REFILL_RATE * dt).min(MAX_AIR);
+        }
+
+        let hazard = if *game_mode == GameMode::Creative {
+            None
+        } else if player.position.y < VOID_DEPTH {
+            Some((VOID_DAMAGE, DamageCause::Void))
+        } else if health.air <= 0.0 {
+            Some((DROWNING_DAMAGE, DamageCause::Drowning))
+        } else {
+            None
+        };
+        let Some((amount, cause)) = hazard else {
+            health.hazard_time = 0.0;
+            continue;
+        };
+        // The first hit lands as soon as the hazard starts
+        if health.hazard_time == 0.0 {
+            health.hurt(amount, cause);
+        }
+        health.hazard_time += dt;
+        if health.hazard_time >= HAZARD_INTERVAL {
+            health.hazard_time -= HAZARD_INTERVAL;
+            health.hurt(amount, cause);
+        }
+    }
+}
+
+/// Heals the player a little at a time once they haven't been hurt for a while,
+/// as long as they're well fed. Healing makes them hungrier.
+pub fn regenerate_health(
+    game_state: Res<GameState>,
+    time: Res<Time>,
+    mut query: Query<(&mut Health, &mut Hunger), With<FlyCam>>,
+) {
+    if *game_state == GameState::Paused || *game_state == GameState::Dead {
+        return;
+    }
+    let dt = time.delta_secs();
+
+    for (mut health, mut hunger) in &mut query {
+        health.since_damage += dt;
+        if health.since_damage >= REGEN_DELAY
+            && health.current < health.max
+            && hunger.food >= REGEN_MIN_HUNGER
+        {
+            let healed = (REGEN_RATE * dt).min(health.max - health.current);
+            health.current += healed;
+            hunger.exhaust(healed * REGEN_EXHAUSTION);
+        }
+    }
+}
+
+/// Applies the damage queued by `Health::hurt`, reporting each hit and, when health
+/// runs out, the death.
+pub fn apply_damage(
+    mut game_state: ResMut<GameState>,
+    mut ev_damaged: EventWriter<PlayerDamagedEvent>,
+    mut ev_died: EventWriter<PlayerDiedEvent>,
+    mut query: Query<(&Transform, &Player, &mut Health), With<FlyCam>>,
+) {
+    for (transform, player, mut health) in &mut query {
+        let pending = std::mem::take(&mut health.pending);
+        for (amount, cause) in pending {
+            if health.is_dead() {
+                break;
+            }
+            health.current = (health.current - amount).max(0.0);
+            health.since_damage = 0.0;
+            ev_damaged.send(PlayerDamagedEvent {
+                amount,
+                cause,
+                health: health.current,
+                player: player.location(transform),
+            });
+            if health.is_dead() {
+                *game_state = GameState::Dead;
+                ev_died.send(PlayerDiedEvent {
+                    cause,
+                    player: player.location(transform),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_falls_are_free_and_longer_ones_hurt_more() {
+        // Jumping lands at the speed it leaves with
+        assert_eq!(fall_damage(-9.0, false), 0.0);
+        assert_eq!(fall_damage(SAFE_LANDING_SPEED, false), 0.0);
+
+        // Landing after falling `h` blocks is at sqrt(2 * gravity * h)
+        let landing = |blocks: f32| -(2.0 * 32.0 * blocks).sqrt();
+        assert_eq!(fall_damage(landing(3.0), false), 0.0);
+        let ten = fall_damage(landing(10.0), false);
+        let twenty = fall_damage(landing(20.0), false);
+        assert!(ten > 0.0 && twenty > ten);
+        assert!(fall_damage(landing(60.0), false) >= MAX_HEALTH);
+        // Unless they land in water
+        assert_eq!(fall_damage(landing(20.0), true), 0.0);
+
+        let mut health = Health::default();
+        health.hurt(0.0, DamageCause::Fall);
+        health.hurt(3.0, DamageCause::Void);
+        assert_eq!(health.pending, vec![(3.0, DamageCause::Void)]);
+        health.current = 0.0;
+        assert!(health.is_dead());
+        health.restore();
+        assert_eq!(health.current, MAX_HEALTH);
+        assert!(health.pending.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::events::PlayerMovedEvent;
+use crate::inventory::Inventory;
+use crate::player::camera::{FlyCam, GameMode, GameState, Player};
+use crate::player::health::{DamageCause, Health};
+
+/// Hunger of a well-fed player, two points to an icon. Saturation can't exceed it.
+pub const MAX_HUNGER: f32 = 20.0;
+/// Exhaustion it takes to cost a point of saturation, or of hunger once
+/// saturation is gone.
+const EXHAUSTION_PER_POINT: f32 = 4.0;
+/// Exhaustion per block walked, swum or sprinted, and per jump.
+const WALK_EXHAUSTION: f32 = 0.01;
+const SPRINT_EXHAUSTION: f32 = 0.1;
+pub const JUMP_EXHAUSTION: f32 = 0.05;
+pub const SPRINT_JUMP_EXHAUSTION: f32 = 0.2;
+/// Health only regenerates with at least this much hunger, and costs this much
+/// exhaustion per point healed.
+pub const REGEN_MIN_HUNGER: f32 = 18.0;
+pub const REGEN_EXHAUSTION: f32 = 6.0;
+/// Damage dealt by an empty stomach each time `STARVE_INTERVAL` passes.
+const STARVE_DAMAGE: f32 = 1.0;
+const STARVE_INTERVAL: f32 = 4.0;
+
+#[derive(Component)]
+pub struct Hunger {
+    pub food: f32,
+    /// Spent before `food`, so a good meal keeps the bar full for a while.
+    pub saturation: f32,
+    /// Builds up with activity until it costs a point of saturation or food.
+    exhaustion: f32,
+    /// Time spent starving since it last hurt.
+    starve_time: f32,
+}
+
+impl Default for Hunger {
+    fn default() -> Self {
+        Self {
+            food: MAX_HUNGER,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            starve_time: 0.0,
+        }
+    }
+}
+
+impl Hunger {
+    /// Adds `amount` of exhaustion, spending saturation and then food for every
+    /// `EXHAUSTION_PER_POINT` of it.
+    pub fn exhaust(&mut self, amount: f32) {
+        self.exhaustion += amount;
+        while self.exhaustion >= EXHAUSTION_PER_POINT {
+            self.exhaustion -= EXHAUSTION_PER_POINT;
+            if self.saturation > 0.0 {
+                self.saturation = (self.saturation - 1.0).max(0.0);
+            } else {
+                self.food = (self.food - 1.0).max(0.0);
+            }
+        }
+    }
+
+    /// Eats something restoring `food` hunger and `saturation`.
+    pub fn eat(&mut self, food: f32, saturation: f32) {
+        self.food = (self.food + food).min(MAX_HUNGER);
+        self.saturation = (self.saturation + saturation).min(self.food);
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.food >= MAX_HUNGER
+    }
+}
+
+/// Walking, swimming and (more so) sprinting make the player hungry.
+pub fn exhaust_on_movement(
+    game_mode: Res<GameMode>,
+    mut ev_moved: EventReader<PlayerMovedEvent>,
+    mut query: Query<(&Player, &mut Hunger), With<FlyCam>>,
+) {
+    let Ok((player, mut hunger)) = query.get_single_mut() else {
+        return;
+    };
+    for event in ev_moved.read() {
+        if *game_mode != GameMode::Survival {
+            continue;
+        }
+        let distance = (event.new_position - event.old_position).xz().length();
+        let rate = if player.sprinting {
+            SPRINT_EXHAUSTION
+        } else {
+            WALK_EXHAUSTION
+        };
+        hunger.exhaust(distance * rate);
+    }
+}
+
+/// An empty stomach slowly hurts.
+pub fn starve(
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    time: Res<Time>,
+    mut query: Query<(&mut Hunger, &mut Health), With<FlyCam>>,
+) {
+    if *game_state == GameState::Paused || *game_state == GameState::Dead {
+        return;
+    }
+
+    for (mut hunger, mut health) in &mut query {
+        if *game_mode != GameMode::Survival || hunger.food > 0.0 {
+            hunger.starve_time = 0.0;
+            continue;
+        }
+        hunger.starve_time += time.delta_secs();
+        if hunger.starve_time >= STARVE_INTERVAL {
+            hunger.starve_time -= STARVE_INTERVAL;
+            health.hurt(STARVE_DAMAGE, DamageCause::Starvation);
+        }
+    }
+}
+
+/// Right-clicking with food in hand eats one of it, unless the player is full.
+pub fn eat_food(
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut inventory: ResMut<Inventory>,
+    mut query: Query<&mut Hunger, With<FlyCam>>,
+) {
+    if *game_state != GameState::Playing
+        || *game_mode != GameMode::Survival
+        || !mouse.just_pressed(MouseButton::Right)
+    {
+        return;
+    }
+    let Some((food, saturation)) = inventory.active_item().and_then(|block| block.food()) else {
+        return;
+    };
+
+    for mut hunger in &mut query {
+        if hunger.is_full() {
+            continue;
+        }
+        hunger.eat(food, saturation);
+        inventory.consume_active();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exhaustion_spends_saturation_before_food() {
+        let mut hunger = Hunger {
+            food: 10.0,
+            saturation: 1.0,
+            ..default()
+        };
+        hunger.exhaust(EXHAUSTION_PER_POINT * 0.5);
+        assert_eq!((hunger.food, hunger.saturation), (10.0, 1.0));
+        hunger.exhaust(EXHAUSTION_PER_POINT * 2.5);
+        assert_eq!((hunger.food, hunger.saturation), (8.0, 0.0));
+
+        // Saturation never outgrows the food it comes with
+        hunger.eat(4.0, 20.0);
+        assert_eq!((hunger.food, hunger.saturation), (12.0, 12.0));
+        hunger.eat(20.0, 0.0);
+        assert!(hunger.is_full());
+    }
+}
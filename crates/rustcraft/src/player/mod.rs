@@ -1,11 +1,15 @@
 pub mod camera;
+pub mod health;
+pub mod hunger;
 
 use bevy::prelude::*;
 use camera::{
     CameraSettings, GameMode, GameState, camera_look, camera_movement, enforce_cursor_state,
     initial_cursor_grab, pause_on_focus_lost, spawn_camera, toggle_gamemode, toggle_inventory,
-    toggle_pause,
+    toggle_pause, update_sneak, update_sprint, update_sprint_fov,
 };
+use health::{apply_damage, hazard_damage, regenerate_health};
+use hunger::{eat_food, exhaust_on_movement, starve};
 
 pub struct PlayerPlugin;
 
@@ -19,11 +23,23 @@ impl Plugin for PlayerPlugin {
                 Update,
                 (
                     camera_look,
-                    camera_movement.after(camera_look),
+                    update_sneak,
+                    update_sprint.after(update_sneak),
+                    camera_movement.after(camera_look).after(update_sprint),
+                    update_sprint_fov.after(update_sprint),
                     toggle_pause,
                     toggle_inventory,
                     toggle_gamemode,
                     pause_on_focus_lost,
+                    hazard_damage,
+                    regenerate_health,
+                    exhaust_on_movement.after(camera_movement),
+                    starve,
+                    eat_food,
+                    apply_damage
+                        .after(camera_movement)
+                        .after(hazard_damage)
+                        .after(starve),
                 ),
             )
             .add_systems(Last, enforce_cursor_state);
@@ -0,0 +1,285 @@
+use bevy::asset::io::file::FileAssetReader;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use image::{Rgba, RgbaImage, imageops};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::world::block::BlockType;
+use crate::world::model::BlockModel;
+
+/// Edge length of one atlas tile in pixels. Block images of other sizes are scaled
+/// to fit.
+pub const TILE_SIZE: u32 = 16;
+
+/// Blocks that get a tile in the atlas. The rest borrow the tiles of their
+/// `BlockType::texture_source`.
+const TEXTURED_BLOCKS: [BlockType; 12] = [
+    BlockType::Grass,
+    BlockType::Dirt,
+    BlockType::Stone,
+    BlockType::Sand,
+    BlockType::Water,
+    BlockType::Wood,
+    BlockType::Leaves,
+    BlockType::Flower,
+    BlockType::TallGrass,
+    BlockType::GlassPane,
+    BlockType::Apple,
+    BlockType::Bread,
+];
+
+/// Which image of a block a face shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockFace {
+    Top,
+    Bottom,
+    Side,
+}
+
+impl BlockFace {
+    pub const ALL: [BlockFace; 3] = [BlockFace::Top, BlockFace::Bottom, BlockFace::Side];
+
+    /// File name suffix of the image used for this face, e.g. `grass_top.png`.
+    fn suffix(self) -> &'static str {
+        match self {
+            BlockFace::Top => "top",
+            BlockFace::Bottom => "bottom",
+            BlockFace::Side => "side",
+        }
+    }
+}
+
+/// `assets/textures/blocks` under Bevy's asset root.
+pub fn default_texture_dir() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets/textures/blocks")
+}
+
+/// All block textures packed into one image. Each face looks for
+/// `<block>_<face>.png` and then `<block>.png` in the texture directory, and falls
+/// back to a tile filled with `BlockType::color` if neither exists.
+#[derive(Resource)]
+pub struct BlockAtlas {
+    pub image: RgbaImage,
+    columns: u32,
+    rows: u32,
+    tiles: HashMap<(BlockType, BlockFace), u32>,
+}
+
+impl BlockAtlas {
+    pub fn load(dir: impl AsRef<Path>) -> Self {
+        let dir = dir.as_ref();
+        Self::build(|block, face| {
+            let name = block.name();
+            [
+                format!("{}_{}.png", name, face.suffix()),
+                format!("{}.png", name),
+            ]
+            .into_iter()
+            .map(|file| dir.join(file))
+            .find(|path| path.exists())
+            .and_then(|path| match image::open(&path) {
+                Ok(image) => Some(image.to_rgba8()),
+                Err(err) => {
+                    warn!("Failed to load block texture {:?}: {}", path, err);
+                    None
+                }
+            })
+        })
+    }
+
+    /// An atlas of flat block colors, without reading any files.
+    pub fn from_colors() -> Self {
+        Self::build(|_, _| None)
+    }
+
+    fn build(mut load: impl FnMut(BlockType, BlockFace) -> Option<RgbaImage>) -> Self {
+        // Faces sharing an image share a tile
+        let mut images: Vec<RgbaImage> = Vec::new();
+        let mut tiles = HashMap::new();
+        for block in TEXTURED_BLOCKS {
+            for face in BlockFace::ALL {
+                let tile = load(block, face)
+                    .map(|image| imageops::resize(&image, TILE_SIZE, TILE_SIZE, imageops::Nearest))
+                    .unwrap_or_else(|| color_tile(block));
+                let index = match images.iter().position(|existing| *existing == tile) {
+                    Some(index) => index,
+                    None => {
+                        images.push(tile);
+                        images.len() - 1
+                    }
+                };
+                tiles.insert((block, face), index as u32);
+            }
+        }
+
+        let columns = (images.len() as f32).sqrt().ceil() as u32;
+        let rows = (images.len() as u32).div_ceil(columns);
+        let mut image = RgbaImage::new(columns * TILE_SIZE, rows * TILE_SIZE);
+        for (i, tile) in images.iter().enumerate() {
+            let i = i as u32;
+            imageops::replace(
+                &mut image,
+                tile,
+                ((i % columns) * TILE_SIZE) as i64,
+                ((i / columns) * TILE_SIZE) as i64,
+            );
+        }
+
+        Self {
+            image,
+            columns,
+            rows,
+            tiles,
+        }
+    }
+
+    /// Size of one tile in UV units.
+    pub fn tile_size(&self) -> Vec2 {
+        Vec2::new(1.0 / self.columns as f32, 1.0 / self.rows as f32)
+    }
+
+    /// UV of the top-left corner of the tile a block face shows.
+    pub fn tile_origin(&self, block: BlockType, face: BlockFace) -> Vec2 {
+        let index = self
+            .tiles
+            .get(&(block.texture_source(), face))
+            .copied()
+            .unwrap_or(0);
+        Vec2::new((index % self.columns) as f32, (index / self.columns) as f32) * self.tile_size()
+    }
+
+    /// The atlas as a GPU texture with nearest-neighbor sampling.
+    pub fn to_texture(&self) -> Image {
+        let mut texture = Image::new(
+            Extent3d {
+                width: self.image.width(),
+                height: self.image.height(),
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            self.image.as_raw().clone(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+        );
+        texture.sampler = ImageSampler::nearest();
+        texture
+    }
+}
+
+fn color_tile(block: BlockType) -> RgbaImage {
+    let color = block.color().to_srgba().to_u8_array();
+    if block.is_food() {
+        return food_tile(block, Rgba(color));
+    }
+    if block.model() == BlockModel::Cross {
+        return plant_tile(block, Rgba(color));
+    }
+    RgbaImage::from_pixel(TILE_SIZE, TILE_SIZE, Rgba(color))
+}
+
+/// A fallback plant on a clear background: blades of grass, or a stem with a
+/// blossom for flowers.
+fn plant_tile(block: BlockType, color: Rgba<u8>) -> RgbaImage {
+    let stem = Rgba(BlockType::TallGrass.color().to_srgba().to_u8_array());
+    let mut tile = RgbaImage::new(TILE_SIZE, TILE_SIZE);
+    if block == BlockType::Flower {
+        for y in TILE_SIZE / 3..TILE_SIZE {
+            tile.put_pixel(TILE_SIZE / 2, y, stem);
+        }
+        for y in TILE_SIZE / 8..TILE_SIZE / 3 + 1 {
+            for x in TILE_SIZE / 2 - 2..=TILE_SIZE / 2 + 2 {
+                tile.put_pixel(x, y, color);
+            }
+        }
+    } else {
+        // Every other column, of uneven heights
+        for x in (1..TILE_SIZE).step_by(2) {
+            let height = TILE_SIZE / 2 + (x * 7) % (TILE_SIZE / 2);
+            for y in TILE_SIZE - height..TILE_SIZE {
+                tile.put_pixel(x, y, color);
+            }
+        }
+    }
+    tile
+}
+
+/// A fallback food item on a clear background: a round apple with a stem, or a
+/// loaf of bread.
+fn food_tile(block: BlockType, color: Rgba<u8>) -> RgbaImage {
+    let mut tile = RgbaImage::new(TILE_SIZE, TILE_SIZE);
+    let center = TILE_SIZE as f32 / 2.0;
+    for y in 0..TILE_SIZE {
+        for x in 0..TILE_SIZE {
+            let dx = (x as f32 + 0.5 - center) / center;
+            let dy = (y as f32 + 0.5 - center) / center;
+            let inside = if block == BlockType::Apple {
+                dx * dx + (dy - 0.15) * (dy - 0.15) < 0.55
+            } else {
+                dx.abs() < 0.85 && dy > -0.4 && dy < 0.5 && dx * dx + dy * dy < 1.0
+            };
+            if inside {
+                tile.put_pixel(x, y, color);
+            }
+        }
+    }
+    if block == BlockType::Apple {
+        let stem = Rgba(BlockType::Wood.color().to_srgba().to_u8_array());
+        for y in 1..TILE_SIZE / 4 {
+            tile.put_pixel(TILE_SIZE / 2, y, stem);
+        }
+    }
+    tile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faces_fall_back_to_block_image_then_color() {
+        let grass_top = RgbaImage::from_pixel(8, 8, Rgba([0, 255, 0, 255]));
+        let grass = RgbaImage::from_pixel(32, 32, Rgba([90, 60, 30, 255]));
+        let atlas = BlockAtlas::build(|block, face| match (block, face) {
+            (BlockType::Grass, BlockFace::Top) => Some(grass_top.clone()),
+            (BlockType::Grass, _) => Some(grass.clone()),
+            _ => None,
+        });
+
+        let pixel = |block, face| {
+            let uv = atlas.tile_origin(block, face);
+            let x = (uv.x * atlas.image.width() as f32) as u32 + 1;
+            let y = (uv.y * atlas.image.height() as f32) as u32 + 1;
+            *atlas.image.get_pixel(x, y)
+        };
+        assert_eq!(
+            pixel(BlockType::Grass, BlockFace::Top),
+            Rgba([0, 255, 0, 255])
+        );
+        assert_eq!(
+            pixel(BlockType::Grass, BlockFace::Side),
+            Rgba([90, 60, 30, 255])
+        );
+        assert_eq!(
+            pixel(BlockType::Stone, BlockFace::Bottom),
+            Rgba(BlockType::Stone.color().to_srgba().to_u8_array())
+        );
+
+        // Identical tiles are stored once: two grass images, six flat colors and
+        // five fallbacks for plants, glass and food
+        assert_eq!(
+            atlas.tile_origin(BlockType::Grass, BlockFace::Side),
+            atlas.tile_origin(BlockType::Grass, BlockFace::Bottom)
+        );
+        assert_eq!(atlas.tiles.values().max(), Some(&12));
+        assert_eq!(atlas.image.width(), 4 * TILE_SIZE);
+
+        // Shaped blocks borrow the tiles of the block they are made of
+        assert_eq!(
+            atlas.tile_origin(BlockType::StoneSlab, BlockFace::Top),
+            atlas.tile_origin(BlockType::Stone, BlockFace::Top)
+        );
+    }
+}
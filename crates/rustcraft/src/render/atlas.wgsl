@@ -0,0 +1,30 @@
+// Chunk meshes merge faces into quads spanning several blocks. `uv` counts blocks
+// across the quad and `uv_b` is the atlas tile it shows; wrap `uv` into the tile
+// so the texture repeats once per block instead of stretching.
+
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+
+@group(2) @binding(100) var<uniform> tile_size: vec2<f32>;
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var tiled = in;
+#ifdef VERTEX_UVS_B
+    tiled.uv = in.uv_b + fract(in.uv) * tile_size;
+#endif
+
+    var pbr_input = pbr_input_from_standard_material(tiled, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
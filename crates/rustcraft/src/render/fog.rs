@@ -0,0 +1,98 @@
+use bevy::pbr::{DistanceFog, FogFalloff};
+use bevy::prelude::*;
+
+use super::sky::sky_color;
+use crate::player::camera::FlyCam;
+use crate::world::chunk::{CHUNK_SIZE, ChunkMap};
+use crate::world::time::WorldTime;
+
+/// Fog color and density while the camera is inside water.
+const UNDERWATER_COLOR: Color = Color::srgb(0.08, 0.22, 0.45);
+const UNDERWATER_VISIBILITY: f32 = 24.0;
+
+/// How the camera's distance fog fades terrain into the sky.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct FogSettings {
+    /// Distance in blocks at which terrain has all but disappeared into the sky.
+    pub view_distance: f32,
+    /// Scales how thick the fog is; 1 hides terrain at `view_distance`, higher
+    /// values hide it sooner and 0 turns the fog off.
+    pub density: f32,
+}
+
+impl Default for FogSettings {
+    fn default() -> Self {
+        Self {
+            view_distance: 12.0 * CHUNK_SIZE as f32,
+            density: 1.0,
+        }
+    }
+}
+
+impl FogSettings {
+    pub fn falloff(&self) -> FogFalloff {
+        falloff(self.view_distance / self.density)
+    }
+}
+
+/// Fog that is about 98% opaque at `visibility` and barely there in the first
+/// third of it.
+fn falloff(visibility: f32) -> FogFalloff {
+    FogFalloff::ExponentialSquared {
+        density: 2.0 / visibility,
+    }
+}
+
+pub fn add_camera_fog(mut commands: Commands, cameras: Query<Entity, Added<FlyCam>>) {
+    for camera in &cameras {
+        commands.entity(camera).insert(DistanceFog::default());
+    }
+}
+
+/// Colors the fog like the sky at the horizon, or thickens it into murky blue
+/// while the camera is under water.
+pub fn update_camera_fog(
+    fog_settings: Res<FogSettings>,
+    world_time: Res<WorldTime>,
+    chunk_map: Res<ChunkMap>,
+    mut cameras: Query<(&Transform, &mut DistanceFog), With<FlyCam>>,
+) {
+    for (transform, mut fog) in &mut cameras {
+        let eye = transform.translation.floor().as_ivec3();
+        if chunk_map.get_block(eye.x, eye.y, eye.z).is_fluid() {
+            fog.color = UNDERWATER_COLOR;
+            fog.falloff = falloff(UNDERWATER_VISIBILITY);
+        } else if fog_settings.density > 0.0 {
+            fog.color = sky_color(world_time.time_of_day());
+            fog.falloff = fog_settings.falloff();
+        } else {
+            fog.color = Color::NONE;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terrain_fades_out_near_the_view_distance() {
+        let settings = FogSettings::default();
+        let FogFalloff::ExponentialSquared { density } = settings.falloff() else {
+            panic!("unexpected falloff");
+        };
+        let fog = |distance: f32| 1.0 - (-(distance * density).powi(2)).exp();
+
+        assert!(fog(settings.view_distance) > 0.95);
+        assert!(fog(settings.view_distance / 3.0) < 0.4);
+
+        let thick = FogSettings {
+            density: 2.0,
+            ..settings
+        };
+        let FogFalloff::ExponentialSquared { density: thick } = thick.falloff() else {
+            panic!("unexpected falloff");
+        };
+        assert!(thick > density);
+    }
+}
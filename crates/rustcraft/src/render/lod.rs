@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use super::RemeshQueue;
+use super::mesh::MeshSettings;
+use crate::player::camera::FlyCam;
+use crate::world::chunk::{CHUNK_SIZE, ChunkMap, ChunkPos};
+
+/// Horizontal distance in chunks from the camera at which chunk meshes switch from
+/// each level of detail to the next coarser one.
+pub const LOD_DISTANCES: [f32; 3] = [4.0, 8.0, 16.0];
+
+/// How far past a switching distance the camera has to move before a chunk
+/// switches, so chunks on the boundary don't flip back and forth.
+const LOD_HYSTERESIS: f32 = 0.5;
+
+/// Level of detail of a chunk mesh. Level `n` draws cells of `2^n` blocks along each
+/// axis, so level 0 is full detail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Lod(pub u8);
+
+impl Lod {
+    pub const MAX: Lod = Lod(LOD_DISTANCES.len() as u8);
+
+    /// Edge length of a cell in blocks.
+    pub fn scale(self) -> usize {
+        1 << self.0
+    }
+
+    /// The level of detail for a chunk `distance` chunks from the camera.
+    pub fn for_distance(distance: f32) -> Lod {
+        Lod(LOD_DISTANCES.iter().filter(|&&d| distance > d).count() as u8)
+    }
+
+    /// The level of detail for a chunk currently drawn at `self` that is now
+    /// `distance` chunks from the camera, only switching once the distance is
+    /// `LOD_HYSTERESIS` past the boundary.
+    pub fn update(self, distance: f32) -> Lod {
+        let mut level = self.0;
+        while level < Lod::MAX.0 && distance > LOD_DISTANCES[level as usize] + LOD_HYSTERESIS {
+            level += 1;
+        }
+        while level > 0 && distance < LOD_DISTANCES[level as usize - 1] - LOD_HYSTERESIS {
+            level -= 1;
+        }
+        Lod(level)
+    }
+}
+
+/// The level of detail every chunk is meshed at.
+#[derive(Resource, Default)]
+pub struct ChunkLods {
+    pub lods: HashMap<ChunkPos, Lod>,
+}
+
+impl ChunkLods {
+    pub fn get(&self, pos: ChunkPos) -> Lod {
+        self.lods.get(&pos).copied().unwrap_or_default()
+    }
+}
+
+/// Picks each chunk's level of detail from its distance to the camera and queues
+/// chunks that switch for remeshing, along with their neighbors whose seams
+/// depend on it.
+pub fn update_chunk_lods(
+    mesh_settings: Res<MeshSettings>,
+    chunk_map: Res<ChunkMap>,
+    camera: Query<&Transform, With<FlyCam>>,
+    mut chunk_lods: ResMut<ChunkLods>,
+    mut queue: ResMut<RemeshQueue>,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    let camera_chunk = camera.translation.xz() / CHUNK_SIZE as f32;
+
+    chunk_lods
+        .lods
+        .retain(|pos, _| chunk_map.chunks.contains_key(pos));
+
+    for &pos in chunk_map.chunks.keys() {
+        let center = Vec2::new(pos.0 as f32 + 0.5, pos.1 as f32 + 0.5);
+        let distance = center.distance(camera_chunk);
+        let current = chunk_lods.lods.get(&pos).copied();
+        let lod = match current {
+            _ if !mesh_settings.lod => Lod(0),
+            Some(current) => current.update(distance),
+            None => Lod::for_distance(distance),
+        };
+        if current == Some(lod) {
+            continue;
+        }
+
+        chunk_lods.lods.insert(pos, lod);
+        // New chunks are already queued by `sync_chunk_entities`
+        if current.is_some() {
+            for dx in -1..=1 {
+                for dz in -1..=1 {
+                    queue.push(ChunkPos(pos.0 + dx, pos.1 + dz));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_has_hysteresis() {
+        assert_eq!(Lod::for_distance(1.0), Lod(0));
+        assert_eq!(Lod::for_distance(5.0), Lod(1));
+        assert_eq!(Lod::for_distance(100.0), Lod::MAX);
+
+        // Just past the boundary keeps the current level either way
+        assert_eq!(Lod(0).update(4.2), Lod(0));
+        assert_eq!(Lod(1).update(3.8), Lod(1));
+        assert_eq!(Lod(0).update(4.6), Lod(1));
+        assert_eq!(Lod(1).update(3.4), Lod(0));
+
+        // Jumps skip levels when the camera moves far at once
+        assert_eq!(Lod(0).update(20.0), Lod(3));
+        assert_eq!(Lod(3).update(1.0), Lod(0));
+    }
+}
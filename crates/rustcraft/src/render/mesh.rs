@@ -2,12 +2,43 @@ use bevy::prelude::*;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use bevy::render::render_asset::RenderAssetUsages;
 
-use crate::world::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, ChunkMap, ChunkPos};
+use super::atlas::{BlockAtlas, BlockFace};
+use super::lod::{ChunkLods, Lod};
+use crate::world::block::BlockType;
+use crate::world::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, Chunk, ChunkMap, ChunkPos};
+use crate::world::model::{BlockModel, HORIZONTAL, ModelBox};
 
 struct FaceDef {
     normal: [f32; 3],
     vertices: [[f32; 3]; 4],
     neighbor_offset: [i32; 3],
+    texture: BlockFace,
+}
+
+impl FaceDef {
+    /// The axis the face points along.
+    fn axis(&self) -> usize {
+        self.neighbor_offset.iter().position(|&o| o != 0).unwrap()
+    }
+
+    fn side(&self) -> IVec3 {
+        IVec3::from_array(self.neighbor_offset)
+    }
+
+    /// Texture coordinates of a corner of the face stretched over `size` blocks, in
+    /// blocks. Side faces keep the texture upright.
+    fn uv(&self, vertex: [f32; 3], size: [usize; 3]) -> [f32; 2] {
+        match self.axis() {
+            1 => [vertex[0] * size[0] as f32, vertex[2] * size[2] as f32],
+            axis => {
+                let u = 2 - axis;
+                [
+                    vertex[u] * size[u] as f32,
+                    (1.0 - vertex[1]) * size[1] as f32,
+                ]
+            }
+        }
+    }
 }
 
 const FACES: [FaceDef; 6] = [
@@ -21,6 +52,7 @@ const FACES: [FaceDef; 6] = [
             [0.0, 1.0, 1.0],
         ],
         neighbor_offset: [0, 1, 0],
+        texture: BlockFace::Top,
     },
     // Bottom (-Y)
     FaceDef {
@@ -32,6 +64,7 @@ const FACES: [FaceDef; 6] = [
             [0.0, 0.0, 0.0],
         ],
         neighbor_offset: [0, -1, 0],
+        texture: BlockFace::Bottom,
     },
     // North (+Z)
     FaceDef {
@@ -43,6 +76,7 @@ const FACES: [FaceDef; 6] = [
             [1.0, 1.0, 1.0],
         ],
         neighbor_offset: [0, 0, 1],
+        texture: BlockFace::Side,
     },
     // South (-Z)
     FaceDef {
@@ -54,6 +88,7 @@ const FACES: [FaceDef; 6] = [
             [0.0, 1.0, 0.0],
         ],
         neighbor_offset: [0, 0, -1],
+        texture: BlockFace::Side,
     },
     // East (+X)
     FaceDef {
@@ -65,6 +100,7 @@ const FACES: [FaceDef; 6] = [
             [1.0, 1.0, 0.0],
         ],
         neighbor_offset: [1, 0, 0],
+        texture: BlockFace::Side,
     },
     // West (-X)
     FaceDef {
@@ -76,78 +112,1211 @@ const FACES: [FaceDef; 6] = [
             [0.0, 1.0, 1.0],
         ],
         neighbor_offset: [-1, 0, 0],
+        texture: BlockFace::Side,
     },
 ];
 
-pub fn build_chunk_mesh(chunk_pos: ChunkPos, chunk_map: &ChunkMap) -> Mesh {
-    let mut positions: Vec<[f32; 3]> = Vec::new();
-    let mut normals: Vec<[f32; 3]> = Vec::new();
-    let mut colors: Vec<[f32; 4]> = Vec::new();
-    let mut indices: Vec<u32> = Vec::new();
+/// Blocks a chunk mesh is built from, in chunk-local coordinates that may reach one
+/// block past the chunk on every side.
+pub trait BlockSource {
+    fn block(&self, x: i32, y: i32, z: i32) -> BlockType;
+
+    /// Number of blocks (or cells) along each axis.
+    fn dims(&self) -> [usize; 3] {
+        [CHUNK_SIZE, CHUNK_HEIGHT, CHUNK_SIZE]
+    }
+
+    /// Edge length of a block in world units; more than 1 for downsampled chunks.
+    fn scale(&self) -> usize {
+        1
+    }
+}
+
+/// A copy of a chunk plus the one-block border of its eight neighbors, so meshing
+/// never has to look blocks up in the `ChunkMap`. The layers below and above the
+/// world are air.
+///
+/// At a coarser `Lod` each entry is a cell of `lod.scale()` blocks along each axis.
+/// The border is then taken from each neighbor as that neighbor is drawn, so the
+/// faces on both sides of a seam between different levels meet without cracks.
+pub struct PaddedChunk {
+    blocks: Vec<BlockType>,
+    size: usize,
+    height: usize,
+    scale: usize,
+}
 
-    let world_offset_x = chunk_pos.0 * CHUNK_SIZE as i32;
-    let world_offset_z = chunk_pos.1 * CHUNK_SIZE as i32;
+impl PaddedChunk {
+    pub fn new(chunk_pos: ChunkPos, chunk_map: &ChunkMap) -> Self {
+        Self::with_lod(chunk_pos, chunk_map, Lod(0), |_| Lod(0))
+    }
 
-    for y in 0..CHUNK_HEIGHT {
-        for z in 0..CHUNK_SIZE {
-            for x in 0..CHUNK_SIZE {
-                let wx = world_offset_x + x as i32;
-                let wy = y as i32;
-                let wz = world_offset_z + z as i32;
+    /// The chunk downsampled to `lod`, with the neighbors drawn at `neighbor_lod`.
+    pub fn with_lod(
+        chunk_pos: ChunkPos,
+        chunk_map: &ChunkMap,
+        lod: Lod,
+        neighbor_lod: impl Fn(ChunkPos) -> Lod,
+    ) -> Self {
+        let scale = lod.scale();
+        let size = CHUNK_SIZE / scale;
+        let height = CHUNK_HEIGHT / scale;
+        let mut padded = Self {
+            blocks: vec![BlockType::Air; (size + 2) * (size + 2) * (height + 2)],
+            size,
+            height,
+            scale,
+        };
 
-                let block = chunk_map.get_block(wx, wy, wz);
-                if !block.is_solid() {
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let neighbor = ChunkPos(chunk_pos.0 + dx, chunk_pos.1 + dz);
+                let Some(chunk) = chunk_map.chunks.get(&neighbor) else {
                     continue;
+                };
+                let neighbor_scale = if (dx, dz) == (0, 0) {
+                    scale
+                } else {
+                    neighbor_lod(neighbor).scale()
+                };
+                // The part of this chunk that falls inside the padded area
+                let range = |d: i32| match d {
+                    -1 => -1..0,
+                    0 => 0..size as i32,
+                    _ => size as i32..size as i32 + 1,
+                };
+
+                for y in 0..height {
+                    for z in range(dz) {
+                        for x in range(dx) {
+                            let local = [
+                                (x * scale as i32).rem_euclid(CHUNK_SIZE as i32) as usize,
+                                y * scale,
+                                (z * scale as i32).rem_euclid(CHUNK_SIZE as i32) as usize,
+                            ];
+                            let block = if neighbor_scale >= scale {
+                                // The (possibly larger) cell this one lies in
+                                let cell = local.map(|l| l / neighbor_scale * neighbor_scale);
+                                downsample(chunk, cell, neighbor_scale)
+                            } else {
+                                least_solid(chunk, local, scale)
+                            };
+                            let index = padded.index(x, y as i32, z);
+                            padded.blocks[index] = block;
+                        }
+                    }
+                }
+            }
+        }
+
+        padded
+    }
+
+    fn index(&self, x: i32, y: i32, z: i32) -> usize {
+        (x + 1) as usize
+            + (z + 1) as usize * (self.size + 2)
+            + (y + 1) as usize * (self.size + 2) * (self.size + 2)
+    }
+}
+
+impl BlockSource for PaddedChunk {
+    #[inline]
+    fn block(&self, x: i32, y: i32, z: i32) -> BlockType {
+        self.blocks[self.index(x, y, z)]
+    }
+
+    fn dims(&self) -> [usize; 3] {
+        [self.size, self.height, self.size]
+    }
+
+    fn scale(&self) -> usize {
+        self.scale
+    }
+}
+
+/// The block a cell of `scale` blocks starting at chunk-local `origin` is drawn as:
+/// air unless at least half of it is filled, otherwise the block most of its
+/// columns show on top, so grass stays grass from afar. Only cubes count; slabs,
+/// fences, plants and the like are too small to show.
+fn downsample(chunk: &Chunk, origin: [usize; 3], scale: usize) -> BlockType {
+    if scale == 1 {
+        return chunk.get_block(origin[0], origin[1], origin[2]);
+    }
+
+    let mut filled = 0;
+    let mut tops: Vec<(BlockType, usize)> = Vec::new();
+    for z in origin[2]..origin[2] + scale {
+        for x in origin[0]..origin[0] + scale {
+            let mut top = None;
+            for y in origin[1]..origin[1] + scale {
+                let block = chunk.get_block(x, y, z);
+                if block.model() == BlockModel::Cube {
+                    filled += 1;
+                    top = Some(block);
+                }
+            }
+            if let Some(top) = top {
+                match tops.iter_mut().find(|(block, _)| *block == top) {
+                    Some((_, count)) => *count += 1,
+                    None => tops.push((top, 1)),
+                }
+            }
+        }
+    }
+
+    if filled * 2 < scale * scale * scale {
+        return BlockType::Air;
+    }
+    tops.iter()
+        .max_by_key(|(_, count)| *count)
+        .map_or(BlockType::Air, |(block, _)| *block)
+}
+
+/// How a neighbor drawn in smaller cells looks from a cell of `scale` blocks:
+/// transparent if any of its blocks is, so the coarser side draws a face wherever
+/// one of the finer cells could be open.
+fn least_solid(chunk: &Chunk, origin: [usize; 3], scale: usize) -> BlockType {
+    let mut transparent = None;
+    for y in origin[1]..origin[1] + scale {
+        for z in origin[2]..origin[2] + scale {
+            for x in origin[0]..origin[0] + scale {
+                let block = chunk.get_block(x, y, z);
+                if block == BlockType::Air {
+                    return block;
                 }
+                if !block.is_opaque_cube() {
+                    transparent = Some(block);
+                }
+            }
+        }
+    }
+    transparent.unwrap_or_else(|| downsample(chunk, origin, scale))
+}
+
+/// Vertex brightness for each ambient occlusion level, from a corner boxed in by
+/// both sides to a fully open one.
+const AO_BRIGHTNESS: [f32; 4] = [0.45, 0.65, 0.82, 1.0];
+
+/// How chunk meshes are built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mesher {
+    /// One quad per visible block face.
+    Naive,
+    /// Merges coplanar adjacent faces of the same block into larger quads.
+    #[default]
+    Greedy,
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshSettings {
+    pub mesher: Mesher,
+    /// Darkens vertices in corners and along edges.
+    pub ambient_occlusion: bool,
+    /// Meshes distant chunks from downsampled blocks (see `Lod`).
+    pub lod: bool,
+}
+
+impl Default for MeshSettings {
+    fn default() -> Self {
+        Self {
+            mesher: Mesher::Greedy,
+            ambient_occlusion: true,
+            lod: true,
+        }
+    }
+}
+
+impl MeshSettings {
+    pub fn build(
+        &self,
+        chunk_pos: ChunkPos,
+        chunk_map: &ChunkMap,
+        chunk_lods: &ChunkLods,
+        atlas: &BlockAtlas,
+    ) -> ChunkMeshes {
+        let build = match self.mesher {
+            Mesher::Naive => build_chunk_mesh::<PaddedChunk>,
+            Mesher::Greedy => build_chunk_mesh_greedy::<PaddedChunk>,
+        };
+        let blocks = if self.lod {
+            let lod = chunk_lods.get(chunk_pos);
+            PaddedChunk::with_lod(chunk_pos, chunk_map, lod, |pos| chunk_lods.get(pos))
+        } else {
+            PaddedChunk::new(chunk_pos, chunk_map)
+        };
+        let ao = self.ambient_occlusion;
+        ChunkMeshes {
+            opaque: build(&blocks, MeshPass::Opaque, atlas, ao),
+            translucent: build(&blocks, MeshPass::Translucent, atlas, ao),
+        }
+    }
+}
+
+/// Which render pass a chunk mesh is drawn in. Translucent blocks (water) go in a
+/// separate alpha-blended mesh so they don't hide what is behind them.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeshPass {
+    Opaque,
+    Translucent,
+}
+
+pub struct ChunkMeshes {
+    pub opaque: Mesh,
+    pub translucent: Mesh,
+}
+
+impl ChunkMeshes {
+    pub fn get(&self, pass: MeshPass) -> &Mesh {
+        match pass {
+            MeshPass::Opaque => &self.opaque,
+            MeshPass::Translucent => &self.translucent,
+        }
+    }
+}
+
+/// Vertex data of a block mesh. Chunk meshes are drawn with a `BlockMaterial`:
+/// `uvs` count blocks across a (possibly merged) face and `tile_origins` holds the
+/// atlas tile the face repeats.
+#[derive(Default)]
+struct MeshData {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    tile_origins: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// Adds a face of the given pass. Translucent faces get a copy facing the other
+    /// way so they can be seen from inside the block (e.g. the water surface from
+    /// below) while both copies are back-face culled, so no pixel is blended twice.
+    fn push_face(
+        &mut self,
+        pass: MeshPass,
+        face: &FaceDef,
+        origin: [usize; 3],
+        size: [usize; 3],
+        tile: Vec2,
+        ao: [u8; 4],
+    ) {
+        let corners = face
+            .vertices
+            .map(|vertex| [0, 1, 2].map(|a| vertex[a] * size[a] as f32 + origin[a] as f32));
+        let uvs = face.vertices.map(|vertex| face.uv(vertex, size));
+        self.push_sides(pass, corners, face.normal, uvs, tile, ao);
+    }
+
+    /// Adds the quad of a `FaceDef` side of `model_box` in the block at `origin`.
+    /// The texture shows the part of the tile the box face covers.
+    fn push_box_face(
+        &mut self,
+        pass: MeshPass,
+        face: &FaceDef,
+        model_box: &ModelBox,
+        origin: [usize; 3],
+        tile: Vec2,
+    ) {
+        let size = model_box.max - model_box.min;
+        let local = face
+            .vertices
+            .map(|vertex| model_box.min + Vec3::from_array(vertex) * size);
+        let corners = local.map(|p| [0, 1, 2].map(|a| p[a] + origin[a] as f32));
+        let uvs = local.map(|p| face.uv(p.to_array(), [1, 1, 1]));
+        self.push_sides(pass, corners, face.normal, uvs, tile, [3; 4]);
+    }
+
+    /// Adds a quad in the given pass, see `push_face`.
+    fn push_sides(
+        &mut self,
+        pass: MeshPass,
+        corners: [[f32; 3]; 4],
+        normal: [f32; 3],
+        uvs: [[f32; 2]; 4],
+        tile: Vec2,
+        ao: [u8; 4],
+    ) {
+        self.push_quad(corners, normal, uvs, tile, ao, false);
+        if pass == MeshPass::Translucent {
+            self.push_quad(corners, normal, uvs, tile, ao, true);
+        }
+    }
+
+    /// Adds a quad with the given corners, listed clockwise seen from the side
+    /// `normal` points to, turned around if `back` is set. `ao` is the occlusion
+    /// level of each corner.
+    fn push_quad(
+        &mut self,
+        corners: [[f32; 3]; 4],
+        normal: [f32; 3],
+        uvs: [[f32; 2]; 4],
+        tile: Vec2,
+        ao: [u8; 4],
+        back: bool,
+    ) {
+        let normal = if back { normal.map(|n| -n) } else { normal };
+        let base_index = self.positions.len() as u32;
+
+        for ((corner, uv), level) in corners.into_iter().zip(uvs).zip(ao) {
+            self.positions.push(corner);
+            self.normals.push(normal);
+            self.uvs.push(uv);
+            self.tile_origins.push(tile.to_array());
+            let brightness = AO_BRIGHTNESS[level as usize];
+            self.colors.push([brightness, brightness, brightness, 1.0]);
+        }
+
+        // Corners come clockwise seen from outside; emit them
+        // counter-clockwise so the front face points along the normal. Split the
+        // quad along the diagonal with the brighter corners so a single dark
+        // corner fades out instead of streaking along the diagonal.
+        let flip = ao[1] + ao[3] > ao[0] + ao[2];
+        let order = match (back, flip) {
+            (false, false) => [0, 2, 1, 0, 3, 2],
+            (false, true) => [0, 3, 1, 1, 3, 2],
+            (true, false) => [0, 1, 2, 0, 2, 3],
+            (true, true) => [0, 1, 3, 1, 2, 3],
+        };
+        self.indices
+            .extend(order.iter().map(|&corner| base_index + corner));
+    }
+
+    /// Scales the mesh of a downsampled chunk back up to world units.
+    fn scale(&mut self, scale: usize) {
+        if scale > 1 {
+            for position in &mut self.positions {
+                *position = position.map(|p| p * scale as f32);
+            }
+        }
+    }
+
+    fn into_mesh(self) -> Mesh {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, self.tile_origins);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.insert_indices(Indices::U32(self.indices));
+        mesh
+    }
+}
+
+/// A block face that gets drawn. Greedy meshing only merges faces that are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VisibleFace {
+    block: BlockType,
+    /// Ambient occlusion level of each corner of the face, 3 being unoccluded.
+    ao: [u8; 4],
+}
+
+/// The face of the block at chunk-local `(x, y, z)` towards `face`, if the block is
+/// a cube drawn in `pass` and the face is visible. Faces between two blocks of the
+/// same translucent type (e.g. inside a lake) are skipped.
+fn visible_face(
+    blocks: &impl BlockSource,
+    [x, y, z]: [usize; 3],
+    face: &FaceDef,
+    pass: MeshPass,
+    ambient_occlusion: bool,
+) -> Option<VisibleFace> {
+    let (x, y, z) = (x as i32, y as i32, z as i32);
+
+    let block = blocks.block(x, y, z);
+    let block_pass = if block.is_translucent() {
+        MeshPass::Translucent
+    } else {
+        MeshPass::Opaque
+    };
+    if block.model() != BlockModel::Cube || block_pass != pass {
+        return None;
+    }
+
+    let neighbor = blocks.block(
+        x + face.neighbor_offset[0],
+        y + face.neighbor_offset[1],
+        z + face.neighbor_offset[2],
+    );
+    if neighbor.hides_face(-face.side()) || neighbor == block {
+        return None;
+    }
+
+    let ao = if ambient_occlusion {
+        face_ao(blocks, [x, y, z], face)
+    } else {
+        [3; 4]
+    };
+    Some(VisibleFace { block, ao })
+}
+
+/// Classic voxel ambient occlusion: each corner of a face is darkened by the two
+/// blocks beside it and the one diagonal to it in the layer the face looks into.
+fn face_ao(blocks: &impl BlockSource, block: [i32; 3], face: &FaceDef) -> [u8; 4] {
+    let axis = face.axis();
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let occludes = |offset: [i32; 3]| {
+        let b = blocks.block(
+            block[0] + face.neighbor_offset[0] + offset[0],
+            block[1] + face.neighbor_offset[1] + offset[1],
+            block[2] + face.neighbor_offset[2] + offset[2],
+        );
+        b.is_opaque_cube()
+    };
 
-                let block_color = block.color().to_linear();
-                let color_arr = [
-                    block_color.red,
-                    block_color.green,
-                    block_color.blue,
-                    block_color.alpha,
-                ];
+    face.vertices.map(|vertex| {
+        let mut side_u = [0; 3];
+        side_u[u] = if vertex[u] > 0.5 { 1 } else { -1 };
+        let mut side_v = [0; 3];
+        side_v[v] = if vertex[v] > 0.5 { 1 } else { -1 };
+        let corner = [0, 1, 2].map(|a| side_u[a] + side_v[a]);
 
+        match (occludes(side_u), occludes(side_v)) {
+            (true, true) => 0,
+            (a, b) => 3 - a as u8 - b as u8 - occludes(corner) as u8,
+        }
+    })
+}
+
+/// Inset of the crossed quads of plants from the corners of their cell.
+const CROSS_INSET: f32 = 0.15;
+
+/// Adds the geometry of a block whose model isn't a plain cube, in the cell at
+/// `origin`. `neighbors` are the blocks beside it in `HORIZONTAL` order and
+/// `hidden(side)` tells whether a face flush with that side of the cell is covered
+/// by the neighbor there. Box faces against or inside another box of the same
+/// model are skipped as well.
+fn push_model(
+    data: &mut MeshData,
+    block: BlockType,
+    neighbors: [BlockType; 4],
+    origin: [usize; 3],
+    pass: MeshPass,
+    atlas: &BlockAtlas,
+    hidden: impl Fn(IVec3) -> bool,
+) {
+    let model = block.model();
+    if model == BlockModel::Cross {
+        let tile = atlas.tile_origin(block, BlockFace::Side);
+        let (a, b) = (CROSS_INSET, 1.0 - CROSS_INSET);
+        for [(x0, z0), (x1, z1)] in [[(a, a), (b, b)], [(b, a), (a, b)]] {
+            let corners = [[x0, 0.0, z0], [x1, 0.0, z1], [x1, 1.0, z1], [x0, 1.0, z0]]
+                .map(|c| [0, 1, 2].map(|i| c[i] + origin[i] as f32));
+            let uvs = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+            // Lit like the ground they grow from, whichever side they are seen from
+            data.push_sides(pass, corners, [0.0, 1.0, 0.0], uvs, tile, [3; 4]);
+        }
+        return;
+    }
+
+    let boxes = model.boxes(neighbors);
+    for (i, model_box) in boxes.iter().enumerate() {
+        for face in &FACES {
+            let axis = face.axis();
+            let positive = face.neighbor_offset[axis] > 0;
+            let plane = if positive {
+                model_box.max[axis]
+            } else {
+                model_box.min[axis]
+            };
+            if (plane == if positive { 1.0 } else { 0.0 }) && hidden(face.side()) {
+                continue;
+            }
+
+            let covered = boxes.iter().enumerate().any(|(j, other)| {
+                // The face is hidden unless it lies on the other box's surface facing out
+                let behind = if positive {
+                    other.min[axis] <= plane && plane < other.max[axis]
+                } else {
+                    other.min[axis] < plane && plane <= other.max[axis]
+                };
+                j != i
+                    && behind
+                    && [0, 1, 2].into_iter().filter(|&a| a != axis).all(|a| {
+                        other.min[a] <= model_box.min[a] && model_box.max[a] <= other.max[a]
+                    })
+            });
+            if !covered {
+                let tile = atlas.tile_origin(block, face.texture);
+                data.push_box_face(pass, face, model_box, origin, tile);
+            }
+        }
+    }
+}
+
+/// Adds every block of the chunk drawn in `pass` whose model isn't a cube.
+fn push_chunk_models(
+    data: &mut MeshData,
+    blocks: &impl BlockSource,
+    pass: MeshPass,
+    atlas: &BlockAtlas,
+) {
+    let [size_x, size_y, size_z] = blocks.dims();
+    for y in 0..size_y {
+        for z in 0..size_z {
+            for x in 0..size_x {
+                let pos = IVec3::new(x as i32, y as i32, z as i32);
+                let block = blocks.block(pos.x, pos.y, pos.z);
+                let block_pass = if block.is_translucent() {
+                    MeshPass::Translucent
+                } else {
+                    MeshPass::Opaque
+                };
+                if matches!(block.model(), BlockModel::Empty | BlockModel::Cube)
+                    || block_pass != pass
+                {
+                    continue;
+                }
+
+                let neighbor = |side: IVec3| {
+                    let p = pos + side;
+                    blocks.block(p.x, p.y, p.z)
+                };
+                let hidden = |side: IVec3| {
+                    let n = neighbor(side);
+                    // Joined panes don't draw the glass between them twice
+                    n.hides_face(-side) || (n == block && block.is_translucent())
+                };
+                push_model(
+                    data,
+                    block,
+                    HORIZONTAL.map(neighbor),
+                    [x, y, z],
+                    pass,
+                    atlas,
+                    hidden,
+                );
+            }
+        }
+    }
+}
+
+pub fn build_chunk_mesh<B: BlockSource>(
+    blocks: &B,
+    pass: MeshPass,
+    atlas: &BlockAtlas,
+    ambient_occlusion: bool,
+) -> Mesh {
+    let [size_x, size_y, size_z] = blocks.dims();
+    let mut data = MeshData::default();
+
+    for y in 0..size_y {
+        for z in 0..size_z {
+            for x in 0..size_x {
                 for face in &FACES {
-                    let nx = wx + face.neighbor_offset[0];
-                    let ny = wy + face.neighbor_offset[1];
-                    let nz = wz + face.neighbor_offset[2];
+                    let pos = [x, y, z];
+                    if let Some(visible) = visible_face(blocks, pos, face, pass, ambient_occlusion)
+                    {
+                        let tile = atlas.tile_origin(visible.block, face.texture);
+                        data.push_face(pass, face, pos, [1, 1, 1], tile, visible.ao);
+                    }
+                }
+            }
+        }
+    }
+    push_chunk_models(&mut data, blocks, pass, atlas);
+
+    data.scale(blocks.scale());
+    data.into_mesh()
+}
 
-                    let neighbor = chunk_map.get_block(nx, ny, nz);
-                    if !neighbor.is_transparent() {
+/// Builds the same surface as `build_chunk_mesh`, but sweeps each layer of faces
+/// pointing the same way and merges runs of the same block and ambient occlusion
+/// into rectangles.
+pub fn build_chunk_mesh_greedy<B: BlockSource>(
+    blocks: &B,
+    pass: MeshPass,
+    atlas: &BlockAtlas,
+    ambient_occlusion: bool,
+) -> Mesh {
+    let dims = blocks.dims();
+    let mut data = MeshData::default();
+
+    for face in &FACES {
+        // The axis the face points along and the two axes spanning its plane
+        let normal_axis = face.axis();
+        let (u, v) = ((normal_axis + 1) % 3, (normal_axis + 2) % 3);
+        let mut mask = vec![None; dims[u] * dims[v]];
+
+        for layer in 0..dims[normal_axis] {
+            for j in 0..dims[v] {
+                for i in 0..dims[u] {
+                    let mut pos = [0; 3];
+                    pos[normal_axis] = layer;
+                    pos[u] = i;
+                    pos[v] = j;
+                    mask[i + j * dims[u]] =
+                        visible_face(blocks, pos, face, pass, ambient_occlusion);
+                }
+            }
+
+            for j in 0..dims[v] {
+                let mut i = 0;
+                while i < dims[u] {
+                    let Some(visible) = mask[i + j * dims[u]] else {
+                        i += 1;
                         continue;
-                    }
+                    };
 
-                    let base_index = positions.len() as u32;
+                    let mut width = 1;
+                    while i + width < dims[u] && mask[i + width + j * dims[u]] == Some(visible) {
+                        width += 1;
+                    }
+                    let mut height = 1;
+                    while j + height < dims[v]
+                        && (i..i + width).all(|k| mask[k + (j + height) * dims[u]] == Some(visible))
+                    {
+                        height += 1;
+                    }
 
-                    for vertex in &face.vertices {
-                        positions.push([
-                            vertex[0] + x as f32,
-                            vertex[1] + y as f32,
-                            vertex[2] + z as f32,
-                        ]);
-                        normals.push(face.normal);
-                        colors.push(color_arr);
+                    for dj in 0..height {
+                        for k in i..i + width {
+                            mask[k + (j + dj) * dims[u]] = None;
+                        }
                     }
 
-                    indices.push(base_index);
-                    indices.push(base_index + 1);
-                    indices.push(base_index + 2);
-                    indices.push(base_index);
-                    indices.push(base_index + 2);
-                    indices.push(base_index + 3);
+                    let mut origin = [0; 3];
+                    origin[normal_axis] = layer;
+                    origin[u] = i;
+                    origin[v] = j;
+                    let mut size = [1; 3];
+                    size[u] = width;
+                    size[v] = height;
+                    let tile = atlas.tile_origin(visible.block, face.texture);
+                    data.push_face(pass, face, origin, size, tile, visible.ao);
+
+                    i += width;
                 }
             }
         }
     }
+    push_chunk_models(&mut data, blocks, pass, atlas);
 
-    let mut mesh = Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
-    );
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
-    mesh.insert_indices(Indices::U32(indices));
+    data.scale(blocks.scale());
+    data.into_mesh()
+}
+
+/// A single block of edge `size` centered on the origin, for item and icon cubes.
+/// Its UVs point straight into the atlas, so it works with a plain
+/// `StandardMaterial` using the atlas texture. Fences and panes are shown joined
+/// east to west.
+pub fn build_block_mesh(block: BlockType, atlas: &BlockAtlas, size: f32) -> Mesh {
+    let mut data = MeshData::default();
+    if block.model() == BlockModel::Cube {
+        for face in &FACES {
+            let tile = atlas.tile_origin(block, face.texture);
+            data.push_face(MeshPass::Opaque, face, [0, 0, 0], [1, 1, 1], tile, [3; 4]);
+        }
+    } else {
+        let neighbors = [BlockType::Air, BlockType::Air, block, block];
+        push_model(
+            &mut data,
+            block,
+            neighbors,
+            [0, 0, 0],
+            MeshPass::Opaque,
+            atlas,
+            |_| false,
+        );
+    }
+
+    for position in &mut data.positions {
+        *position = position.map(|p| (p - 0.5) * size);
+    }
+    let tile_size = atlas.tile_size();
+    for (uv, origin) in data.uvs.iter_mut().zip(&data.tile_origins) {
+        *uv = (Vec2::from(*origin) + Vec2::from(*uv) * tile_size).to_array();
+    }
+
+    let mut mesh = data.into_mesh();
+    mesh.remove_attribute(Mesh::ATTRIBUTE_UV_1);
     mesh
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::generation::WorldGen;
+    use crate::world::pipeline::GenerationPipeline;
+    use bevy::render::mesh::VertexAttributeValues;
+    use std::collections::HashSet;
+
+    /// A unit face: the block, its normal, the atlas tile and the shade of each
+    /// corner of the quad it belongs to.
+    type CoveredFace = ([i32; 3], [i32; 3], [u32; 2], [u32; 4]);
+
+    /// Every unit face a mesh covers. Fails if two quads overlap.
+    fn covered_faces(mesh: &Mesh) -> HashSet<CoveredFace> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("mesh has no normals");
+        };
+        let Some(VertexAttributeValues::Float32x2(tiles)) = mesh.attribute(Mesh::ATTRIBUTE_UV_1)
+        else {
+            panic!("mesh has no atlas tiles");
+        };
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR)
+        else {
+            panic!("mesh has no colors");
+        };
+
+        let mut faces = HashSet::new();
+        for quad in 0..positions.len() / 4 {
+            let corners = &positions[quad * 4..quad * 4 + 4];
+            let normal = normals[quad * 4].map(|n| n as i32);
+            let tile = tiles[quad * 4].map(f32::to_bits);
+            let shade = [0, 1, 2, 3].map(|i| colors[quad * 4 + i][0].to_bits());
+            let min = [0, 1, 2].map(|a| corners.iter().map(|c| c[a] as i32).min().unwrap());
+            let max = [0, 1, 2].map(|a| corners.iter().map(|c| c[a] as i32).max().unwrap());
+
+            // The block behind the face sits one step against the normal on the normal axis
+            let axis = normal.iter().position(|&n| n != 0).unwrap();
+            let mut range = [0, 1, 2].map(|a| min[a]..max[a]);
+            let layer = if normal[axis] > 0 {
+                min[axis] - 1
+            } else {
+                min[axis]
+            };
+            range[axis] = layer..layer + 1;
+
+            for x in range[0].clone() {
+                for y in range[1].clone() {
+                    for z in range[2].clone() {
+                        assert!(
+                            faces.insert(([x, y, z], normal, tile, shade)),
+                            "face of {:?} towards {:?} covered twice",
+                            [x, y, z],
+                            normal
+                        );
+                    }
+                }
+            }
+        }
+        faces
+    }
+
+    /// Looks every block up in the `ChunkMap`, the way meshing worked before
+    /// `PaddedChunk`.
+    struct ChunkMapSource<'a> {
+        chunk_map: &'a ChunkMap,
+        chunk_pos: ChunkPos,
+    }
+
+    impl BlockSource for ChunkMapSource<'_> {
+        fn block(&self, x: i32, y: i32, z: i32) -> BlockType {
+            self.chunk_map.get_block(
+                self.chunk_pos.0 * CHUNK_SIZE as i32 + x,
+                y,
+                self.chunk_pos.1 * CHUNK_SIZE as i32 + z,
+            )
+        }
+    }
+
+    /// Generated terrain with the plants pulled up, so every face covers whole
+    /// blocks.
+    fn generated_world() -> ChunkMap {
+        let world_gen = WorldGen::default();
+        let mut pipeline = GenerationPipeline::default();
+        let mut chunk_map = ChunkMap::default();
+        for cx in -1..=1 {
+            for cz in -1..=1 {
+                pipeline.generate(ChunkPos(cx, cz), &world_gen, &mut chunk_map);
+            }
+        }
+        for chunk in chunk_map.chunks.values_mut() {
+            for y in 0..CHUNK_HEIGHT {
+                for z in 0..CHUNK_SIZE {
+                    for x in 0..CHUNK_SIZE {
+                        if chunk.get_block(x, y, z).model() == BlockModel::Cross {
+                            chunk.set_block(x, y, z, BlockType::Air);
+                        }
+                    }
+                }
+            }
+        }
+        chunk_map
+    }
+
+    #[test]
+    fn greedy_mesh_covers_the_same_faces() {
+        let mut chunk_map = generated_world();
+        let atlas = BlockAtlas::from_colors();
+        // A hole and a pillar so some runs have to stop mid-row
+        chunk_map.set_block(5, 10, 5, BlockType::Air);
+        chunk_map.set_block(8, 60, 8, BlockType::Stone);
+
+        // A pond so the translucent pass has something to merge
+        for x in 2..6 {
+            for z in 2..5 {
+                chunk_map.set_block(x, 40, z, BlockType::Water);
+                chunk_map.set_block(x, 41, z, BlockType::Water);
+            }
+        }
+
+        for pos in [ChunkPos(0, 0), ChunkPos(1, 1)] {
+            for pass in [MeshPass::Opaque, MeshPass::Translucent] {
+                let blocks = PaddedChunk::new(pos, &chunk_map);
+                for ao in [false, true] {
+                    let naive = build_chunk_mesh(&blocks, pass, &atlas, ao);
+                    let greedy = build_chunk_mesh_greedy(&blocks, pass, &atlas, ao);
+
+                    let naive_faces = covered_faces(&naive);
+                    assert_eq!(naive_faces.len(), naive.count_vertices() / 4);
+                    assert_eq!(covered_faces(&greedy), naive_faces);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_mesh_reduces_vertex_count() {
+        let chunk_map = generated_world();
+        let atlas = BlockAtlas::from_colors();
+        let blocks = PaddedChunk::new(ChunkPos(0, 0), &chunk_map);
+        for ao in [false, true] {
+            let naive = build_chunk_mesh(&blocks, MeshPass::Opaque, &atlas, ao).count_vertices();
+            let greedy =
+                build_chunk_mesh_greedy(&blocks, MeshPass::Opaque, &atlas, ao).count_vertices();
+
+            println!(
+                "ambient occlusion {}: naive {} vertices, greedy {} vertices ({:.0}% fewer)",
+                if ao { "on" } else { "off" },
+                naive,
+                greedy,
+                100.0 * (1.0 - greedy as f32 / naive as f32)
+            );
+            // Occlusion gradients stop merges at every edge and corner
+            let max_ratio = if ao { 0.75 } else { 0.5 };
+            assert!((greedy as f32) < naive as f32 * max_ratio);
+        }
+    }
+
+    #[test]
+    fn water_goes_in_the_translucent_pass_without_internal_faces() {
+        let mut chunk = crate::world::chunk::Chunk::new();
+        // A 2x1x1 pool of water resting on a stone block
+        chunk.set_block(0, 0, 0, BlockType::Stone);
+        chunk.set_block(0, 1, 0, BlockType::Water);
+        chunk.set_block(1, 1, 0, BlockType::Water);
+        let mut chunk_map = ChunkMap::default();
+        chunk_map.chunks.insert(ChunkPos(0, 0), chunk);
+        let atlas = BlockAtlas::from_colors();
+
+        let blocks = PaddedChunk::new(ChunkPos(0, 0), &chunk_map);
+        let opaque = build_chunk_mesh(&blocks, MeshPass::Opaque, &atlas, true);
+        let translucent = build_chunk_mesh(&blocks, MeshPass::Translucent, &atlas, true);
+
+        // The stone shows all six faces, its top seen through the water
+        assert_eq!(opaque.count_vertices(), 6 * 4);
+        // 9 outer faces of the pool (none between the two water blocks or against
+        // the stone), each drawn from both sides
+        assert_eq!(translucent.count_vertices(), 9 * 2 * 4);
+        let faces = covered_faces(&translucent);
+        assert!(
+            !faces
+                .iter()
+                .any(|(pos, normal, ..)| *pos == [0, 1, 0] && *normal == [1, 0, 0])
+        );
+    }
+
+    #[test]
+    fn corners_next_to_blocks_are_darkened() {
+        // A stone floor with an L-shaped wall around the corner block (1, 1, 1)
+        let mut chunk = crate::world::chunk::Chunk::new();
+        for x in 0..3 {
+            for z in 0..3 {
+                chunk.set_block(x, 0, z, BlockType::Stone);
+            }
+        }
+        chunk.set_block(1, 1, 0, BlockType::Stone);
+        chunk.set_block(0, 1, 1, BlockType::Stone);
+        let mut chunk_map = ChunkMap::default();
+        chunk_map.chunks.insert(ChunkPos(0, 0), chunk);
+
+        let atlas = BlockAtlas::from_colors();
+        let blocks = PaddedChunk::new(ChunkPos(0, 0), &chunk_map);
+        let mesh = build_chunk_mesh(&blocks, MeshPass::Opaque, &atlas, true);
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR)
+        else {
+            panic!("mesh has no colors");
+        };
+        let Some(Indices::U32(indices)) = mesh.indices() else {
+            panic!("mesh has no indices");
+        };
+
+        // Top face of the floor block (0, 0, 0), boxed in by both wall blocks
+        let quad = (0..positions.len() / 4)
+            .find(|q| {
+                (0..4).all(|i| {
+                    positions[q * 4 + i][1] == 1.0
+                        && positions[q * 4 + i][0] <= 1.0
+                        && positions[q * 4 + i][2] <= 1.0
+                })
+            })
+            .unwrap();
+        let brightness = |x: f32, z: f32| {
+            let i = (0..4)
+                .map(|i| quad * 4 + i)
+                .find(|&i| positions[i][0] == x && positions[i][2] == z)
+                .unwrap();
+            colors[i][0]
+        };
+        assert_eq!(brightness(0.0, 0.0), AO_BRIGHTNESS[3]);
+        assert_eq!(brightness(1.0, 0.0), AO_BRIGHTNESS[2]);
+        assert_eq!(brightness(0.0, 1.0), AO_BRIGHTNESS[2]);
+        assert_eq!(brightness(1.0, 1.0), AO_BRIGHTNESS[0]);
+
+        // The dark corner sits in a single triangle
+        let dark = (0..4)
+            .map(|i| (quad * 4 + i) as u32)
+            .find(|&i| colors[i as usize][0] == AO_BRIGHTNESS[0])
+            .unwrap();
+        let triangles = indices[quad * 6..quad * 6 + 6].chunks(3);
+        assert_eq!(triangles.filter(|t| t.contains(&dark)).count(), 1);
+    }
+
+    #[test]
+    fn shaped_blocks_only_hide_faces_they_cover() {
+        // A stone block with a slab on top and another beside it, a fence next to
+        // the stone, a flower, and two joined panes
+        let mut chunk = crate::world::chunk::Chunk::new();
+        chunk.set_block(0, 0, 0, BlockType::Stone);
+        chunk.set_block(0, 1, 0, BlockType::StoneSlab);
+        chunk.set_block(1, 0, 0, BlockType::StoneSlab);
+        chunk.set_block(0, 0, 1, BlockType::Fence);
+        chunk.set_block(5, 0, 5, BlockType::Flower);
+        chunk.set_block(8, 0, 8, BlockType::GlassPane);
+        chunk.set_block(9, 0, 8, BlockType::GlassPane);
+        let mut chunk_map = ChunkMap::default();
+        chunk_map.chunks.insert(ChunkPos(0, 0), chunk);
+        let atlas = BlockAtlas::from_colors();
+        let blocks = PaddedChunk::new(ChunkPos(0, 0), &chunk_map);
+
+        let quads = |pass| {
+            let mesh = build_chunk_mesh_greedy(&blocks, pass, &atlas, true);
+            let Some(VertexAttributeValues::Float32x3(positions)) =
+                mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            else {
+                panic!("mesh has no positions");
+            };
+            let Some(VertexAttributeValues::Float32x3(normals)) =
+                mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+            else {
+                panic!("mesh has no normals");
+            };
+            (0..positions.len() / 4)
+                .map(|q| {
+                    let center = (0..4)
+                        .map(|i| Vec3::from_array(positions[q * 4 + i]))
+                        .sum::<Vec3>()
+                        / 4.0;
+                    (center, Vec3::from_array(normals[q * 4]))
+                })
+                .collect::<Vec<_>>()
+        };
+        let opaque = quads(MeshPass::Opaque);
+        let has = |center: Vec3, normal: Vec3| opaque.contains(&(center, normal));
+
+        // The slab's bottom covers the stone's top, and both hide their faces there
+        assert!(!has(Vec3::new(0.5, 1.0, 0.5), Vec3::Y));
+        assert!(!has(Vec3::new(0.5, 1.0, 0.5), Vec3::NEG_Y));
+        assert!(has(Vec3::new(0.5, 1.5, 0.5), Vec3::Y));
+        // Half a side doesn't hide the stone beside it, but the stone hides the slab
+        assert!(has(Vec3::new(1.0, 0.5, 0.5), Vec3::X));
+        assert!(!has(Vec3::new(1.0, 0.25, 0.5), Vec3::NEG_X));
+        // Nor does a fence
+        assert!(has(Vec3::new(0.5, 0.5, 1.0), Vec3::Z));
+
+        // All six sides of the fence post show, and four of each rail joining the
+        // stone: their ends are against the stone and inside the post
+        let fence_faces = opaque
+            .iter()
+            .filter(|(center, _)| center.z > 1.0 && center.z < 2.0 && center.x < 1.0)
+            .count();
+        assert_eq!(fence_faces, 6 + 2 * 4);
+
+        // The flower is two quads lit from above
+        let flower = opaque
+            .iter()
+            .filter(|(center, _)| center.x > 5.0 && center.x < 6.0)
+            .collect::<Vec<_>>();
+        assert_eq!(flower.len(), 2);
+        assert!(flower.iter().all(|(_, normal)| *normal == Vec3::Y));
+
+        // The panes are drawn from both sides without glass between them
+        let glass = quads(MeshPass::Translucent);
+        assert!(!glass.iter().any(|(center, _)| center.x == 9.0));
+        assert!(glass.iter().any(|(center, _)| center.x == 8.4375));
+        assert!(glass.iter().any(|(center, _)| center.x == 9.5625));
+    }
+
+    #[test]
+    fn padded_chunk_matches_chunk_map() {
+        let chunk_map = generated_world();
+        let blocks = PaddedChunk::new(ChunkPos(0, 0), &chunk_map);
+        let lookup = ChunkMapSource {
+            chunk_map: &chunk_map,
+            chunk_pos: ChunkPos(0, 0),
+        };
+
+        for y in -1..=CHUNK_HEIGHT as i32 {
+            for z in -1..=CHUNK_SIZE as i32 {
+                for x in -1..=CHUNK_SIZE as i32 {
+                    assert_eq!(blocks.block(x, y, z), lookup.block(x, y, z));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn coarser_lods_have_fewer_vertices() {
+        let chunk_map = generated_world();
+        let atlas = BlockAtlas::from_colors();
+        let mut previous = usize::MAX;
+        for level in 0..=Lod::MAX.0 {
+            let blocks =
+                PaddedChunk::with_lod(ChunkPos(0, 0), &chunk_map, Lod(level), |_| Lod(level));
+            let mesh = build_chunk_mesh_greedy(&blocks, MeshPass::Opaque, &atlas, false);
+            let Some(VertexAttributeValues::Float32x3(positions)) =
+                mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            else {
+                panic!("mesh has no positions");
+            };
+
+            assert!(mesh.count_vertices() > 0);
+            assert!(mesh.count_vertices() < previous);
+            previous = mesh.count_vertices();
+            // Scaled back up to the chunk's size in blocks
+            assert!(positions.iter().all(|p| {
+                (0.0..=CHUNK_SIZE as f32).contains(&p[0])
+                    && (0.0..=CHUNK_HEIGHT as f32).contains(&p[1])
+                    && (0.0..=CHUNK_SIZE as f32).contains(&p[2])
+            }));
+            assert!(positions.iter().any(|p| p[0] == CHUNK_SIZE as f32));
+        }
+    }
+
+    #[test]
+    fn seams_between_lods_have_no_cracks() {
+        let chunk_map = generated_world();
+        let atlas = BlockAtlas::from_colors();
+        let opaque = |block: BlockType| block.is_solid() && !block.is_transparent();
+
+        for (lod_a, lod_b) in [(0, 1), (1, 0), (0, 3), (2, 1), (1, 3)] {
+            // (0, 0) at `lod_a` meets (1, 0) at `lod_b` along x = 16
+            let neighbor_lod = |pos: ChunkPos| Lod(if pos.0 >= 1 { lod_b } else { lod_a });
+            let a = PaddedChunk::with_lod(ChunkPos(0, 0), &chunk_map, Lod(lod_a), neighbor_lod);
+            let b = PaddedChunk::with_lod(ChunkPos(1, 0), &chunk_map, Lod(lod_b), neighbor_lod);
+            let faces_a = covered_faces(&build_chunk_mesh_greedy(
+                &a,
+                MeshPass::Opaque,
+                &atlas,
+                false,
+            ));
+            let faces_b = covered_faces(&build_chunk_mesh_greedy(
+                &b,
+                MeshPass::Opaque,
+                &atlas,
+                false,
+            ));
+            let has_face = |faces: &HashSet<CoveredFace>, pos: [i32; 3], normal: [i32; 3]| {
+                faces.iter().any(|f| f.0 == pos && f.1 == normal)
+            };
+
+            let (scale_a, scale_b) = (a.scale() as i32, b.scale() as i32);
+            for y in 0..CHUNK_HEIGHT as i32 {
+                for z in 0..CHUNK_SIZE as i32 {
+                    // The cells drawn on either side of the unit square at (16, y, z)
+                    let solid_a = opaque(a.block(a.dims()[0] as i32 - 1, y / scale_a, z / scale_a));
+                    let solid_b = opaque(b.block(0, y / scale_b, z / scale_b));
+                    let face_a = has_face(&faces_a, [CHUNK_SIZE as i32 - 1, y, z], [1, 0, 0]);
+                    let face_b = has_face(&faces_b, [0, y, z], [-1, 0, 0]);
+
+                    if solid_a && !solid_b {
+                        assert!(
+                            face_a,
+                            "crack at {:?} with lods {:?}",
+                            [16, y, z],
+                            (lod_a, lod_b)
+                        );
+                    }
+                    if solid_b && !solid_a {
+                        assert!(
+                            face_b,
+                            "crack at {:?} with lods {:?}",
+                            [16, y, z],
+                            (lod_a, lod_b)
+                        );
+                    }
+                    assert!(!(face_a && face_b), "both sides draw {:?}", [16, y, z]);
+                }
+            }
+        }
+    }
+
+    /// Mesh time per chunk looking blocks up in the `ChunkMap` versus copying them
+    /// into a `PaddedChunk` first. Run with
+    /// `cargo test --release mesh_time_per_chunk -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn mesh_time_per_chunk() {
+        use std::time::{Duration, Instant};
+
+        let chunk_map = generated_world();
+        let atlas = BlockAtlas::from_colors();
+        let pos = ChunkPos(0, 0);
+        let runs = 50;
+        let time = |build: &dyn Fn()| -> Duration {
+            let start = Instant::now();
+            for _ in 0..runs {
+                build();
+            }
+            start.elapsed() / runs
+        };
+
+        for mesher in [Mesher::Naive, Mesher::Greedy] {
+            let before = time(&|| {
+                let lookup = ChunkMapSource {
+                    chunk_map: &chunk_map,
+                    chunk_pos: pos,
+                };
+                match mesher {
+                    Mesher::Naive => build_chunk_mesh(&lookup, MeshPass::Opaque, &atlas, true),
+                    Mesher::Greedy => {
+                        build_chunk_mesh_greedy(&lookup, MeshPass::Opaque, &atlas, true)
+                    }
+                };
+            });
+            let after = time(&|| {
+                let blocks = PaddedChunk::new(pos, &chunk_map);
+                match mesher {
+                    Mesher::Naive => build_chunk_mesh(&blocks, MeshPass::Opaque, &atlas, true),
+                    Mesher::Greedy => {
+                        build_chunk_mesh_greedy(&blocks, MeshPass::Opaque, &atlas, true)
+                    }
+                };
+            });
+
+            println!(
+                "{:?} mesher: {:?} per chunk with ChunkMap lookups, {:?} with a padded copy ({:.1}x faster)",
+                mesher,
+                before,
+                after,
+                before.as_secs_f64() / after.as_secs_f64()
+            );
+        }
+    }
+}
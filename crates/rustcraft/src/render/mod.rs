@@ -1,92 +1,376 @@
+pub mod atlas;
+pub mod fog;
+pub mod lod;
 pub mod mesh;
+pub mod sky;
 
+use bevy::asset::embedded_asset;
+use bevy::ecs::system::SystemParam;
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
 use bevy::prelude::*;
 use bevy::render::mesh::MeshAabb;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use crate::world::block::BlockType;
 use crate::world::chunk::{CHUNK_SIZE, ChunkMap, ChunkPos};
-use mesh::build_chunk_mesh;
+use atlas::BlockAtlas;
+use lod::ChunkLods;
+use mesh::{ChunkMeshes, MeshPass, MeshSettings};
 
+/// The material of chunk meshes: a `StandardMaterial` textured with the block atlas
+/// whose fragment shader repeats each atlas tile across merged faces.
+pub type BlockMaterial = ExtendedMaterial<StandardMaterial, AtlasTiling>;
+
+#[derive(Asset, AsBindGroup, TypePath, Debug, Clone)]
+pub struct AtlasTiling {
+    /// Size of one atlas tile in UV units.
+    #[uniform(100)]
+    pub tile_size: Vec2,
+}
+
+impl MaterialExtension for AtlasTiling {
+    fn fragment_shader() -> ShaderRef {
+        "embedded://rustcraft/render/atlas.wgsl".into()
+    }
+}
+
+/// Most chunks meshed in one frame; the rest wait in the `RemeshQueue`.
+const REMESHES_PER_FRAME: usize = 8;
+
+/// GPU handles shared by everything that draws blocks.
+#[derive(Resource)]
+pub struct BlockTextures {
+    pub atlas: Handle<Image>,
+    /// For blocks outside of chunks (dropped items, icons).
+    pub item_material: Handle<StandardMaterial>,
+    /// Like `item_material`, but alpha-blended for translucent blocks like water.
+    pub item_translucent_material: Handle<StandardMaterial>,
+    pub chunk_opaque: Handle<BlockMaterial>,
+    pub chunk_translucent: Handle<BlockMaterial>,
+}
+
+impl BlockTextures {
+    /// The material to draw `block` with outside of chunks.
+    pub fn item_material_for(&self, block: BlockType) -> Handle<StandardMaterial> {
+        if block.is_translucent() {
+            self.item_translucent_material.clone()
+        } else {
+            self.item_material.clone()
+        }
+    }
+}
+
+/// The root entity of a chunk's meshes, with one child per `MeshPass`.
 #[derive(Component)]
 pub struct ChunkEntity(pub ChunkPos);
 
+/// The `ChunkEntity` of every meshed chunk.
+#[derive(Resource, Default)]
+pub struct ChunkEntities {
+    pub entities: HashMap<ChunkPos, Entity>,
+    /// Chunks queued for their first mesh, which don't have an entity yet.
+    pending: HashSet<ChunkPos>,
+}
+
+/// Chunks waiting to be (re)meshed, oldest first.
+#[derive(Resource, Default)]
+pub struct RemeshQueue {
+    queue: VecDeque<ChunkPos>,
+    queued: HashSet<ChunkPos>,
+}
+
+impl RemeshQueue {
+    pub fn push(&mut self, pos: ChunkPos) {
+        if self.queued.insert(pos) {
+            self.queue.push_back(pos);
+        }
+    }
+
+    fn pop(&mut self) -> Option<ChunkPos> {
+        let pos = self.queue.pop_front()?;
+        self.queued.remove(&pos);
+        Some(pos)
+    }
+}
+
 pub struct RenderPlugin;
 
 impl Plugin for RenderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Startup,
-            spawn_chunk_meshes.after(crate::world::generation::generate_world),
-        )
-        .add_systems(Update, remesh_dirty_chunks);
+        embedded_asset!(app, "atlas.wgsl");
+
+        // A `BlockAtlas` inserted before the plugin (e.g. from `--textures`) wins
+        if !app.world().contains_resource::<BlockAtlas>() {
+            app.insert_resource(BlockAtlas::load(atlas::default_texture_dir()));
+        }
+
+        app.add_plugins(MaterialPlugin::<BlockMaterial>::default())
+            .init_resource::<MeshSettings>()
+            .init_resource::<ChunkEntities>()
+            .init_resource::<ChunkLods>()
+            .init_resource::<fog::FogSettings>()
+            .init_resource::<RemeshQueue>()
+            .add_systems(Startup, (setup_block_textures, sky::setup_sky))
+            .add_systems(
+                Update,
+                (
+                    toggle_ambient_occlusion,
+                    sync_chunk_entities,
+                    lod::update_chunk_lods,
+                    queue_dirty_chunks,
+                    remesh_queued_chunks,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    sky::update_sky,
+                    sky::update_sky_dome,
+                    fog::add_camera_fog,
+                    fog::update_camera_fog,
+                ),
+            );
     }
 }
 
-fn spawn_chunk_meshes(
+pub fn setup_block_textures(
     mut commands: Commands,
-    chunk_map: Res<ChunkMap>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    atlas: Res<BlockAtlas>,
+    mut images: ResMut<Assets<Image>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut block_materials: ResMut<Assets<BlockMaterial>>,
 ) {
-    let material = materials.add(StandardMaterial {
-        base_color: Color::WHITE,
-        perceptual_roughness: 0.9,
+    let texture = images.add(atlas.to_texture());
+    // Plants are cut out of their tiles and seen from both sides
+    let item_material = materials.add(StandardMaterial {
+        base_color_texture: Some(texture.clone()),
+        alpha_mode: AlphaMode::Mask(0.1),
+        cull_mode: None,
+        ..default()
+    });
+    let item_translucent_material = materials.add(StandardMaterial {
+        base_color_texture: Some(texture.clone()),
+        alpha_mode: AlphaMode::Blend,
         cull_mode: None,
         ..default()
     });
 
-    for (&chunk_pos, _chunk) in &chunk_map.chunks {
-        let mesh = build_chunk_mesh(chunk_pos, &chunk_map);
-        let mesh_handle = meshes.add(mesh);
+    let tiling = AtlasTiling {
+        tile_size: atlas.tile_size(),
+    };
+    let chunk_opaque = block_materials.add(BlockMaterial {
+        base: StandardMaterial {
+            base_color_texture: Some(texture.clone()),
+            perceptual_roughness: 0.9,
+            alpha_mode: AlphaMode::Mask(0.5),
+            cull_mode: None,
+            ..default()
+        },
+        extension: tiling.clone(),
+    });
+    // Water's alpha comes from its atlas tile
+    let chunk_translucent = block_materials.add(BlockMaterial {
+        base: StandardMaterial {
+            base_color_texture: Some(texture.clone()),
+            perceptual_roughness: 0.3,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        },
+        extension: tiling,
+    });
 
-        commands.spawn((
-            Mesh3d(mesh_handle),
-            MeshMaterial3d(material.clone()),
-            Transform::from_xyz(
-                (chunk_pos.0 * CHUNK_SIZE as i32) as f32,
-                0.0,
-                (chunk_pos.1 * CHUNK_SIZE as i32) as f32,
-            ),
-            ChunkEntity(chunk_pos),
-        ));
-    }
+    commands.insert_resource(BlockTextures {
+        atlas: texture,
+        item_material,
+        item_translucent_material,
+        chunk_opaque,
+        chunk_translucent,
+    });
 }
 
-fn remesh_dirty_chunks(
+/// Queues chunks that were added to the `ChunkMap` for their first mesh and
+/// despawns the meshes of chunks that were removed.
+fn sync_chunk_entities(
     mut commands: Commands,
-    mut chunk_map: ResMut<ChunkMap>,
-    query: Query<(Entity, &ChunkEntity, &Mesh3d)>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    chunk_map: Res<ChunkMap>,
+    mut chunk_entities: ResMut<ChunkEntities>,
+    mut queue: ResMut<RemeshQueue>,
+    chunk_roots: Query<(Entity, &ChunkEntity)>,
 ) {
-    let dirty_positions: Vec<ChunkPos> = chunk_map
-        .chunks
-        .iter()
-        .filter(|(_, c)| c.dirty)
-        .map(|(&pos, _)| pos)
-        .collect();
-
-    if dirty_positions.is_empty() {
+    if !chunk_map.is_changed() {
         return;
     }
 
-    for &chunk_pos in &dirty_positions {
-        let new_mesh = build_chunk_mesh(chunk_pos, &chunk_map);
-
-        for (entity, chunk_entity, mesh3d) in &query {
-            if chunk_entity.0 == chunk_pos {
-                if let Some(mesh) = meshes.get_mut(&mesh3d.0) {
-                    *mesh = new_mesh;
-                    if let Some(aabb) = mesh.compute_aabb() {
-                        commands.entity(entity).insert(aabb);
-                    }
-                    break;
+    // Faces on the border of a chunk depend on its neighbors, so those are remeshed
+    // when a chunk appears or disappears next to them
+    let mut changed = Vec::new();
+    for &pos in chunk_map.chunks.keys() {
+        if !chunk_entities.entities.contains_key(&pos) && chunk_entities.pending.insert(pos) {
+            queue.push(pos);
+            changed.push(pos);
+        }
+    }
+
+    chunk_entities.pending.retain(|pos| {
+        let kept = chunk_map.chunks.contains_key(pos);
+        if !kept {
+            changed.push(*pos);
+        }
+        kept
+    });
+
+    for (entity, chunk) in &chunk_roots {
+        if !chunk_map.chunks.contains_key(&chunk.0) {
+            commands.entity(entity).despawn_recursive();
+            chunk_entities.entities.remove(&chunk.0);
+            changed.push(chunk.0);
+        }
+    }
+
+    for pos in changed {
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let neighbor = ChunkPos(pos.0 + dx, pos.1 + dz);
+                if chunk_entities.entities.contains_key(&neighbor) {
+                    queue.push(neighbor);
                 }
             }
         }
     }
+}
+
+fn queue_dirty_chunks(mut chunk_map: ResMut<ChunkMap>, mut queue: ResMut<RemeshQueue>) {
+    if !chunk_map.chunks.values().any(|c| c.dirty) {
+        return;
+    }
 
-    for pos in dirty_positions {
-        if let Some(chunk) = chunk_map.chunks.get_mut(&pos) {
+    for (&pos, chunk) in chunk_map.chunks.iter_mut() {
+        if chunk.dirty {
             chunk.dirty = false;
+            queue.push(pos);
+        }
+    }
+}
+
+/// Everything needed to build a chunk's meshes and upload them.
+#[derive(SystemParam)]
+struct ChunkMeshing<'w> {
+    chunk_map: Res<'w, ChunkMap>,
+    mesh_settings: Res<'w, MeshSettings>,
+    chunk_lods: Res<'w, ChunkLods>,
+    atlas: Res<'w, BlockAtlas>,
+    textures: Res<'w, BlockTextures>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+}
+
+/// Meshes up to `REMESHES_PER_FRAME` queued chunks, spawning the mesh entities of
+/// chunks that don't have any yet.
+fn remesh_queued_chunks(
+    mut commands: Commands,
+    mut meshing: ChunkMeshing,
+    mut chunk_entities: ResMut<ChunkEntities>,
+    mut queue: ResMut<RemeshQueue>,
+    children: Query<&Children>,
+    passes: Query<(&MeshPass, &Mesh3d)>,
+) {
+    let mut meshed = 0;
+    while meshed < REMESHES_PER_FRAME {
+        let Some(chunk_pos) = queue.pop() else {
+            break;
+        };
+        if !meshing.chunk_map.chunks.contains_key(&chunk_pos) {
+            continue;
+        }
+        meshed += 1;
+
+        let new_meshes = meshing.mesh_settings.build(
+            chunk_pos,
+            &meshing.chunk_map,
+            &meshing.chunk_lods,
+            &meshing.atlas,
+        );
+
+        let Some(&entity) = chunk_entities.entities.get(&chunk_pos) else {
+            let entity = spawn_chunk_entity(
+                &mut commands,
+                chunk_pos,
+                new_meshes,
+                &meshing.textures,
+                &mut meshing.meshes,
+            );
+            chunk_entities.entities.insert(chunk_pos, entity);
+            chunk_entities.pending.remove(&chunk_pos);
+            continue;
+        };
+
+        for &child in children.get(entity).into_iter().flatten() {
+            let Ok((pass, mesh3d)) = passes.get(child) else {
+                continue;
+            };
+            if let Some(mesh) = meshing.meshes.get_mut(&mesh3d.0) {
+                *mesh = new_meshes.get(*pass).clone();
+                if let Some(aabb) = mesh.compute_aabb() {
+                    commands.entity(child).insert(aabb);
+                }
+            }
+        }
+    }
+}
+
+fn spawn_chunk_entity(
+    commands: &mut Commands,
+    chunk_pos: ChunkPos,
+    chunk_meshes: ChunkMeshes,
+    textures: &BlockTextures,
+    meshes: &mut Assets<Mesh>,
+) -> Entity {
+    commands
+        .spawn((
+            ChunkEntity(chunk_pos),
+            Transform::from_xyz(
+                (chunk_pos.0 * CHUNK_SIZE as i32) as f32,
+                0.0,
+                (chunk_pos.1 * CHUNK_SIZE as i32) as f32,
+            ),
+            Visibility::default(),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Mesh3d(meshes.add(chunk_meshes.opaque)),
+                MeshMaterial3d(textures.chunk_opaque.clone()),
+                MeshPass::Opaque,
+            ));
+            parent.spawn((
+                Mesh3d(meshes.add(chunk_meshes.translucent)),
+                MeshMaterial3d(textures.chunk_translucent.clone()),
+                MeshPass::Translucent,
+            ));
+        })
+        .id()
+}
+
+/// F4 switches ambient occlusion on and off, remeshing every chunk.
+fn toggle_ambient_occlusion(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mesh_settings: ResMut<MeshSettings>,
+    mut chunk_map: ResMut<ChunkMap>,
+) {
+    if !keys.just_pressed(KeyCode::F4) {
+        return;
+    }
+
+    mesh_settings.ambient_occlusion = !mesh_settings.ambient_occlusion;
+    info!(
+        "Ambient occlusion {}",
+        if mesh_settings.ambient_occlusion {
+            "on"
+        } else {
+            "off"
         }
+    );
+    for chunk in chunk_map.chunks.values_mut() {
+        chunk.dirty = true;
     }
 }
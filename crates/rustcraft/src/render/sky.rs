@@ -0,0 +1,213 @@
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::player::camera::FlyCam;
+use crate::world::time::WorldTime;
+
+const SUN_ILLUMINANCE: f32 = 15000.0;
+const MOON_ILLUMINANCE: f32 = 400.0;
+const DAY_AMBIENT: f32 = 200.0;
+const NIGHT_AMBIENT: f32 = 25.0;
+
+/// Tilts the sun's path towards +Z so it never stands exactly overhead.
+const SUN_TILT: f32 = 0.3;
+
+const DAY_SKY: Color = Color::srgb(0.53, 0.75, 1.0);
+const NIGHT_SKY: Color = Color::srgb(0.02, 0.02, 0.06);
+const SUNSET_SKY: Color = Color::srgb(0.95, 0.55, 0.3);
+const DAY_ZENITH: Color = Color::srgb(0.22, 0.42, 0.88);
+const NIGHT_ZENITH: Color = Color::srgb(0.0, 0.0, 0.02);
+
+/// Radius of the sky dome: inside the camera's far plane but behind all terrain.
+const SKY_DOME_RADIUS: f32 = 900.0;
+
+/// A directional light that moves across the sky with the time of day.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkyLight {
+    Sun,
+    /// Opposite the sun, lighting the world while the sun is down.
+    Moon,
+}
+
+/// Unit vector pointing at the sun. It rises in the east (+X) at 0.25, stands
+/// highest at noon and sets in the west at 0.75.
+pub fn sun_direction(time_of_day: f32) -> Vec3 {
+    let angle = (time_of_day - 0.25) * TAU;
+    Vec3::new(angle.cos(), angle.sin(), SUN_TILT).normalize()
+}
+
+/// How much of the daylight is up, from 0 at night to 1 once the sun is clear of
+/// the horizon. Twilight fades in shortly before sunrise and out after sunset.
+pub fn daylight(time_of_day: f32) -> f32 {
+    light_level(sun_direction(time_of_day).y)
+}
+
+/// Brightness of a light whose direction has the given height above the horizon.
+fn light_level(height: f32) -> f32 {
+    let t = ((height + 0.1) / 0.35).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Color of the sky at the horizon, reddened while the sun is near it. Also the
+/// clear color and the color distant terrain fades into.
+pub fn sky_color(time_of_day: f32) -> Color {
+    let base = NIGHT_SKY.mix(&DAY_SKY, daylight(time_of_day));
+    let height = sun_direction(time_of_day).y;
+    let sunset = (1.0 - height.abs() / 0.25).max(0.0) * 0.6;
+    base.mix(&SUNSET_SKY, sunset)
+}
+
+/// Color of the sky `height` up from the horizon (0) to straight overhead (1).
+pub fn sky_gradient(time_of_day: f32, height: f32) -> Color {
+    let zenith = NIGHT_ZENITH.mix(&DAY_ZENITH, daylight(time_of_day));
+    sky_color(time_of_day).mix(&zenith, height.clamp(0.0, 1.0).sqrt())
+}
+
+/// A sphere around the camera showing the sky gradient behind everything else.
+#[derive(Component)]
+pub struct SkyDome;
+
+pub fn setup_sky(
+    mut commands: Commands,
+    world_time: Res<WorldTime>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut dome = Sphere::new(SKY_DOME_RADIUS).mesh().uv(32, 18);
+    color_sky_dome(&mut dome, world_time.time_of_day());
+    commands.spawn((
+        Mesh3d(meshes.add(dome)),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            unlit: true,
+            fog_enabled: false,
+            cull_mode: None,
+            ..default()
+        })),
+        Transform::default(),
+        NotShadowCaster,
+        NotShadowReceiver,
+        SkyDome,
+    ));
+
+    commands.spawn((
+        DirectionalLight {
+            illuminance: SUN_ILLUMINANCE,
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::default(),
+        SkyLight::Sun,
+    ));
+    commands.spawn((
+        DirectionalLight {
+            illuminance: 0.0,
+            color: Color::srgb(0.7, 0.75, 1.0),
+            ..default()
+        },
+        Transform::default(),
+        SkyLight::Moon,
+    ));
+
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: DAY_AMBIENT,
+    });
+}
+
+/// Moves the sun and moon and sets light levels and the sky for the time of day.
+pub fn update_sky(
+    world_time: Res<WorldTime>,
+    mut lights: Query<(&SkyLight, &mut DirectionalLight, &mut Transform)>,
+    mut ambient: ResMut<AmbientLight>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    let time_of_day = world_time.time_of_day();
+    let sun_direction = sun_direction(time_of_day);
+    let daylight = daylight(time_of_day);
+
+    for (sky_light, mut light, mut transform) in &mut lights {
+        let (direction, illuminance) = match sky_light {
+            SkyLight::Sun => (sun_direction, SUN_ILLUMINANCE * daylight),
+            SkyLight::Moon => (
+                -sun_direction,
+                MOON_ILLUMINANCE * light_level(-sun_direction.y),
+            ),
+        };
+        light.illuminance = illuminance;
+        *transform = Transform::default().looking_to(-direction, Vec3::Y);
+    }
+
+    ambient.brightness = NIGHT_AMBIENT + (DAY_AMBIENT - NIGHT_AMBIENT) * daylight;
+    clear_color.0 = sky_color(time_of_day);
+}
+
+/// Keeps the sky dome centered on the camera and recolors it as time passes.
+pub fn update_sky_dome(
+    world_time: Res<WorldTime>,
+    cameras: Query<&Transform, (With<FlyCam>, Without<SkyDome>)>,
+    mut domes: Query<(&Mesh3d, &mut Transform), With<SkyDome>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut colored_at: Local<f32>,
+) {
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let time_of_day = world_time.time_of_day();
+    // Only rewrite the colors once the sky has visibly changed
+    let recolor = (time_of_day - *colored_at).abs() > 0.001;
+    if recolor {
+        *colored_at = time_of_day;
+    }
+
+    for (mesh, mut transform) in &mut domes {
+        transform.translation = camera.translation;
+        if recolor && let Some(mesh) = meshes.get_mut(&mesh.0) {
+            color_sky_dome(mesh, time_of_day);
+        }
+    }
+}
+
+fn color_sky_dome(mesh: &mut Mesh, time_of_day: f32) {
+    let Some(positions) = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .and_then(|positions| positions.as_float3())
+    else {
+        return;
+    };
+    let colors: Vec<[f32; 4]> = positions
+        .iter()
+        .map(|p| {
+            sky_gradient(time_of_day, p[1] / SKY_DOME_RADIUS)
+                .to_linear()
+                .to_f32_array()
+        })
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sun_rises_in_the_east_and_sets_in_the_west() {
+        assert!(sun_direction(0.25).x > 0.9);
+        assert!(sun_direction(0.5).y > 0.9);
+        assert!(sun_direction(0.75).x < -0.9);
+        assert!(sun_direction(0.0).y < -0.9);
+
+        assert_eq!(daylight(0.5), 1.0);
+        assert_eq!(daylight(0.0), 0.0);
+        assert!(daylight(0.25) > 0.0 && daylight(0.25) < 1.0);
+
+        let brightness = |color: Color| color.to_linear().luminance();
+        assert!(brightness(sky_color(0.5)) > brightness(sky_color(0.0)));
+        // Sunset is redder than noon
+        assert!(sky_color(0.75).to_srgba().red > sky_color(0.5).to_srgba().red);
+
+        // The dome darkens towards the zenith and meets the fog at the horizon
+        assert!(brightness(sky_gradient(0.5, 1.0)) < brightness(sky_gradient(0.5, 0.0)));
+        assert_eq!(sky_gradient(0.5, -0.5), sky_color(0.5));
+    }
+}
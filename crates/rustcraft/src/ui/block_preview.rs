@@ -4,6 +4,9 @@ use bevy::render::render_resource::{
     Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
 };
 
+use crate::render::BlockTextures;
+use crate::render::atlas::BlockAtlas;
+use crate::render::mesh::build_block_mesh;
 use crate::world::block::BlockType;
 
 const PREVIEW_SIZE: u32 = 64;
@@ -59,6 +62,8 @@ use bevy::render::view::visibility::RenderLayers;
 pub fn setup_block_previews(
     mut commands: Commands,
     mut images: ResMut<Assets<Image>>,
+    atlas: Res<BlockAtlas>,
+    textures: Res<BlockTextures>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
@@ -72,9 +77,31 @@ pub fn setup_block_previews(
         BlockType::Wood,
         BlockType::Leaves,
         BlockType::Water,
+        BlockType::StoneSlab,
+        BlockType::WoodStairs,
+        BlockType::Fence,
+        BlockType::Flower,
+        BlockType::TallGrass,
+        BlockType::GlassPane,
+        BlockType::Apple,
+        BlockType::Bread,
     ];
 
-    let cube_mesh = meshes.add(Cuboid::new(1.0, 1.0, 1.0));
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(textures.atlas.clone()),
+        unlit: true,
+        alpha_mode: AlphaMode::Mask(0.1),
+        cull_mode: None,
+        ..default()
+    });
+    // Translucent blocks like water keep their see-through look in the icons
+    let translucent_material = materials.add(StandardMaterial {
+        base_color_texture: Some(textures.atlas.clone()),
+        unlit: true,
+        alpha_mode: AlphaMode::Blend,
+        cull_mode: None,
+        ..default()
+    });
     let mut previews = BlockPreviews::default();
 
     // Light for all preview cubes
@@ -111,12 +138,12 @@ pub fn setup_block_previews(
         // Cube for this block
         commands.spawn((
             PreviewCube,
-            Mesh3d(cube_mesh.clone()),
-            MeshMaterial3d(materials.add(StandardMaterial {
-                base_color: block.color(),
-                unlit: true,
-                ..default()
-            })),
+            Mesh3d(meshes.add(build_block_mesh(*block, &atlas, 1.0))),
+            MeshMaterial3d(if block.is_translucent() {
+                translucent_material.clone()
+            } else {
+                material.clone()
+            }),
             Transform::from_translation(offset),
             preview_layer.clone(),
         ));
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::events::PlayerDiedEvent;
+use crate::player::camera::{FlyCam, GameState, Player, respawn};
+use crate::player::health::{DamageCause, Health};
+use crate::player::hunger::Hunger;
+
+#[derive(Component)]
+pub struct DeathScreenRoot;
+
+#[derive(Component)]
+pub struct DeathMessage;
+
+#[derive(Component)]
+pub struct RespawnButton;
+
+pub fn spawn_death_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            DeathScreenRoot,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.4, 0.0, 0.0, 0.5)),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("You died!"),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            parent.spawn((
+                DeathMessage,
+                Text::new(""),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.85, 0.85, 0.85)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(24.0)),
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn((
+                    RespawnButton,
+                    Button,
+                    Node {
+                        width: Val::Px(200.0),
+                        height: Val::Px(50.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        Text::new("Respawn"),
+                        TextFont {
+                            font_size: 24.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                });
+        });
+}
+
+fn death_message(cause: DamageCause) -> &'static str {
+    match cause {
+        DamageCause::Fall => "You hit the ground too hard",
+        DamageCause::Drowning => "You drowned",
+        DamageCause::Void => "You fell out of the world",
+        DamageCause::Starvation => "You starved to death",
+    }
+}
+
+pub fn show_hide_death_screen(
+    game_state: Res<GameState>,
+    mut ev_died: EventReader<PlayerDiedEvent>,
+    mut root_query: Query<&mut Visibility, With<DeathScreenRoot>>,
+    mut message_query: Query<&mut Text, With<DeathMessage>>,
+) {
+    for event in ev_died.read() {
+        for mut text in &mut message_query {
+            **text = death_message(event.cause).to_string();
+        }
+    }
+
+    if !game_state.is_changed() {
+        return;
+    }
+    for mut vis in &mut root_query {
+        *vis = match *game_state {
+            GameState::Dead => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+pub fn handle_respawn_button(
+    interaction: Query<&Interaction, (Changed<Interaction>, With<RespawnButton>)>,
+    mut game_state: ResMut<GameState>,
+    mut windows: Query<&mut Window>,
+    mut query: Query<(&mut Transform, &mut Player, &mut Health, &mut Hunger), With<FlyCam>>,
+) {
+    for &inter in &interaction {
+        if inter != Interaction::Pressed {
+            continue;
+        }
+        for (mut transform, mut player, mut health, mut hunger) in &mut query {
+            respawn(&mut transform, &mut player);
+            health.restore();
+            *hunger = Hunger::default();
+        }
+        *game_state = GameState::Playing;
+        if let Ok(mut window) = windows.get_single_mut() {
+            window.cursor_options.grab_mode = bevy::window::CursorGrabMode::Locked;
+            window.cursor_options.visible = false;
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+
+use crate::player::camera::{FlyCam, GameMode, GameState};
+use crate::player::health::{Health, MAX_AIR};
+use crate::player::hunger::{Hunger, MAX_HUNGER};
+use crate::ui::hotbar::HOTBAR_WIDTH;
+
+#[derive(Component)]
+pub struct HealthBarRoot;
+
+/// The filled part of the nth heart.
+#[derive(Component)]
+pub struct HeartFill(pub usize);
+
+/// The filled part of the nth hunger icon, counting from the right.
+#[derive(Component)]
+pub struct HungerFill(pub usize);
+
+#[derive(Component)]
+pub struct BreathRow;
+
+/// The filled part of the nth bubble of breath.
+#[derive(Component)]
+pub struct BubbleFill(pub usize);
+
+const HEARTS: usize = 10;
+const HUNGER_ICONS: usize = 10;
+const BUBBLES: usize = 10;
+const ICON_SIZE: f32 = 14.0;
+const ICON_GAP: f32 = 3.0;
+const HEART_COLOR: Color = Color::srgb(0.85, 0.1, 0.1);
+const HUNGER_COLOR: Color = Color::srgb(0.75, 0.45, 0.15);
+const BUBBLE_COLOR: Color = Color::srgb(0.35, 0.6, 0.95);
+
+fn spawn_icon_row<C: Component>(
+    parent: &mut ChildBuilder,
+    count: usize,
+    color: Color,
+    fill: impl Fn(usize) -> C,
+) {
+    for i in 0..count {
+        parent
+            .spawn((
+                Node {
+                    width: Val::Px(ICON_SIZE),
+                    height: Val::Px(ICON_SIZE),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.7)),
+                BorderColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            ))
+            .with_children(|icon| {
+                icon.spawn((
+                    fill(i),
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(color),
+                ));
+            });
+    }
+}
+
+pub fn spawn_health_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            HealthBarRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(62.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            // Lined up with the ends of the hotbar
+            parent
+                .spawn(Node {
+                    width: Val::Px(HOTBAR_WIDTH),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                })
+                .with_children(|bar| {
+                    bar.spawn(Node {
+                        column_gap: Val::Px(ICON_GAP),
+                        ..default()
+                    })
+                    .with_children(|row| spawn_icon_row(row, HEARTS, HEART_COLOR, HeartFill));
+                    // Hunger on the right, emptying from the left, with breath above it
+                    bar.spawn(Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(ICON_GAP),
+                        ..default()
+                    })
+                    .with_children(|column| {
+                        column
+                            .spawn((
+                                BreathRow,
+                                Node {
+                                    flex_direction: FlexDirection::RowReverse,
+                                    column_gap: Val::Px(ICON_GAP),
+                                    ..default()
+                                },
+                                Visibility::Hidden,
+                            ))
+                            .with_children(|row| {
+                                spawn_icon_row(row, BUBBLES, BUBBLE_COLOR, BubbleFill)
+                            });
+                        column
+                            .spawn(Node {
+                                flex_direction: FlexDirection::RowReverse,
+                                column_gap: Val::Px(ICON_GAP),
+                                ..default()
+                            })
+                            .with_children(|row| {
+                                spawn_icon_row(row, HUNGER_ICONS, HUNGER_COLOR, HungerFill)
+                            });
+                    });
+                });
+        });
+}
+
+/// How full the nth of `icons` icons is when `value` of `max` is left.
+fn icon_fill(index: usize, icons: usize, value: f32, max: f32) -> f32 {
+    let per_icon = max / icons as f32;
+    ((value - index as f32 * per_icon) / per_icon).clamp(0.0, 1.0)
+}
+
+/// The hearts and hunger only matter in survival, and are out of the way in the inventory.
+pub fn show_hide_health_bar(
+    game_state: Res<GameState>,
+    game_mode: Res<GameMode>,
+    mut query: Query<&mut Visibility, With<HealthBarRoot>>,
+) {
+    if !game_state.is_changed() && !game_mode.is_changed() {
+        return;
+    }
+    let visible = *game_mode == GameMode::Survival
+        && matches!(*game_state, GameState::Playing | GameState::Paused);
+    for mut vis in &mut query {
+        *vis = if visible {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn update_health_bar(
+    health_query: Query<&Health, (With<FlyCam>, Changed<Health>)>,
+    mut heart_query: Query<(&HeartFill, &mut Node), Without<BubbleFill>>,
+    mut bubble_query: Query<(&BubbleFill, &mut Node), Without<HeartFill>>,
+    mut breath_query: Query<&mut Visibility, With<BreathRow>>,
+) {
+    let Ok(health) = health_query.get_single() else {
+        return;
+    };
+
+    for (heart, mut node) in &mut heart_query {
+        let fill = icon_fill(heart.0, HEARTS, health.current, health.max);
+        node.width = Val::Percent(fill * 100.0);
+    }
+    for (bubble, mut node) in &mut bubble_query {
+        let fill = icon_fill(bubble.0, BUBBLES, health.air, MAX_AIR);
+        node.width = Val::Percent(fill * 100.0);
+    }
+    // Breath shows only while it's running out or coming back
+    for mut vis in &mut breath_query {
+        *vis = if health.air < MAX_AIR {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn update_hunger_bar(
+    hunger_query: Query<&Hunger, (With<FlyCam>, Changed<Hunger>)>,
+    mut fill_query: Query<(&HungerFill, &mut Node)>,
+) {
+    let Ok(hunger) = hunger_query.get_single() else {
+        return;
+    };
+    for (icon, mut node) in &mut fill_query {
+        let fill = icon_fill(icon.0, HUNGER_ICONS, hunger.food, MAX_HUNGER);
+        node.width = Val::Percent(fill * 100.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hearts_fill_in_halves() {
+        let fills: Vec<f32> = (0..HEARTS)
+            .map(|i| icon_fill(i, HEARTS, 7.0, 20.0))
+            .collect();
+        assert_eq!(&fills[..5], &[1.0, 1.0, 1.0, 0.5, 0.0]);
+        assert!(fills[5..].iter().all(|&fill| fill == 0.0));
+        assert_eq!(icon_fill(HEARTS - 1, HEARTS, 20.0, 20.0), 1.0);
+    }
+}
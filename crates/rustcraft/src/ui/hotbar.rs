@@ -18,6 +18,8 @@ pub struct HotbarSlotCount(pub usize);
 
 const SLOT_SIZE: f32 = 44.0;
 const SLOT_GAP: f32 = 4.0;
+/// Width of the nine slots and the gaps between them.
+pub(crate) const HOTBAR_WIDTH: f32 = 9.0 * SLOT_SIZE + 8.0 * SLOT_GAP;
 const PREVIEW_SIZE: f32 = 32.0;
 
 pub fn spawn_hotbar(mut commands: Commands) {
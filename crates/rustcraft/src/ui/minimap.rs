@@ -0,0 +1,340 @@
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use std::collections::{HashMap, HashSet};
+
+use crate::events::{BlockPlacedEvent, BlockRemovedEvent};
+use crate::map::{map_column, shade};
+use crate::player::camera::{FlyCam, GameState, Player};
+use crate::world::chunk::{CHUNK_SIZE, ChunkMap, ChunkPos};
+
+/// Side of the minimap on screen, and of its image, in pixels.
+const MINIMAP_SIZE: u32 = 128;
+/// Blocks across the minimap at each zoom level, closest first.
+const ZOOM_LEVELS: [f32; 3] = [32.0, 64.0, 128.0];
+const DEFAULT_ZOOM: usize = 1;
+/// How many changed chunks are redrawn per frame.
+const TILES_PER_FRAME: usize = 8;
+const BACKGROUND: [u8; 4] = [20, 20, 24, 255];
+const ARROW_COLOR: [u8; 4] = [255, 255, 255, 255];
+const ARROW_OUTLINE: [u8; 4] = [0, 0, 0, 255];
+const ARROW_LENGTH: f32 = 6.0;
+const ARROW_HALF_WIDTH: f32 = 4.0;
+
+#[derive(Component)]
+pub struct MinimapRoot;
+
+/// The top-down image of every loaded chunk, one tile per chunk, kept up to date
+/// as chunks appear and blocks change.
+#[derive(Resource, Default)]
+pub struct MinimapTiles {
+    /// `CHUNK_SIZE * CHUNK_SIZE` pixels per chunk, indexed by `x + z * CHUNK_SIZE`.
+    tiles: HashMap<ChunkPos, Vec<[u8; 4]>>,
+    stale: HashSet<ChunkPos>,
+    /// Set whenever a tile changed, so the minimap is drawn again.
+    changed: bool,
+}
+
+impl MinimapTiles {
+    /// The pixel of the column at `(x, z)`, if its chunk has been drawn.
+    fn pixel(&self, x: i32, z: i32) -> Option<[u8; 4]> {
+        let size = CHUNK_SIZE as i32;
+        let tile = self
+            .tiles
+            .get(&ChunkPos(x.div_euclid(size), z.div_euclid(size)))?;
+        let (lx, lz) = (x.rem_euclid(size), z.rem_euclid(size));
+        Some(tile[(lx + lz * size) as usize])
+    }
+
+    /// Marks the chunk holding block `(x, z)` for redrawing. The light comes from
+    /// +Z, so the chunk to the -Z side is shaded by this one's edge too.
+    fn block_changed(&mut self, x: i32, z: i32) {
+        let size = CHUNK_SIZE as i32;
+        let pos = ChunkPos(x.div_euclid(size), z.div_euclid(size));
+        self.stale.insert(pos);
+        if z.rem_euclid(size) == 0 {
+            self.stale.insert(ChunkPos(pos.0, pos.1 - 1));
+        }
+    }
+}
+
+/// The minimap's image and what it was last drawn for.
+#[derive(Resource)]
+pub struct Minimap {
+    image: Handle<Image>,
+    zoom: usize,
+    /// The player's position in minimap pixels, the arrow's heading in degrees
+    /// and the zoom level of the last drawing.
+    drawn: Option<(IVec2, i32, usize)>,
+}
+
+/// Draws the columns of `pos` lit from +Z, the top of the minimap.
+fn draw_tile(chunk_map: &ChunkMap, pos: ChunkPos) -> Vec<[u8; 4]> {
+    let size = CHUNK_SIZE as i32;
+    let mut tile = vec![BACKGROUND; CHUNK_SIZE * CHUNK_SIZE];
+    for lz in 0..size {
+        for lx in 0..size {
+            let (x, z) = (pos.0 * size + lx, pos.1 * size + lz);
+            if let Some(column) = map_column(chunk_map, x, z) {
+                tile[(lx + lz * size) as usize] = shade(column, map_column(chunk_map, x, z + 1)).0;
+            }
+        }
+    }
+    tile
+}
+
+/// Draws the minimap around `center` with `blocks` columns across it. North (+Z)
+/// is up, so looking down on the world +X is to the left.
+fn draw_minimap(tiles: &MinimapTiles, center: Vec2, blocks: f32, heading: Vec2) -> Vec<u8> {
+    let blocks_per_pixel = blocks / MINIMAP_SIZE as f32;
+    let half = MINIMAP_SIZE as f32 / 2.0;
+    let mut pixels = Vec::with_capacity((MINIMAP_SIZE * MINIMAP_SIZE * 4) as usize);
+    for py in 0..MINIMAP_SIZE {
+        for px in 0..MINIMAP_SIZE {
+            let offset = (Vec2::new(px as f32, py as f32) + 0.5 - half) * blocks_per_pixel;
+            let x = (center.x - offset.x).floor() as i32;
+            let z = (center.y - offset.y).floor() as i32;
+            let mut pixel = tiles.pixel(x, z).unwrap_or(BACKGROUND);
+
+            // The player's arrow, drawn over the middle
+            let point = Vec2::new(px as f32, py as f32) + 0.5 - half;
+            if in_arrow(point, heading, 1.0) {
+                pixel = ARROW_COLOR;
+            } else if in_arrow(point, heading, 2.0) {
+                pixel = ARROW_OUTLINE;
+            }
+            pixels.extend_from_slice(&pixel);
+        }
+    }
+    pixels
+}
+
+/// Whether `point` (in pixels from the middle of the minimap) is inside the arrow
+/// pointing along `heading`, grown by `outline` pixels.
+fn in_arrow(point: Vec2, heading: Vec2, outline: f32) -> bool {
+    let side = heading.perp();
+    let tip = heading * (ARROW_LENGTH + outline);
+    let left = -heading * (ARROW_LENGTH / 2.0 + outline) + side * (ARROW_HALF_WIDTH + outline);
+    let right = -heading * (ARROW_LENGTH / 2.0 + outline) - side * (ARROW_HALF_WIDTH + outline);
+    let edges = [(tip, left), (left, right), (right, tip)];
+    let signs = edges.map(|(a, b)| (b - a).perp_dot(point - a) >= 0.0);
+    signs.iter().all(|&s| s) || signs.iter().all(|&s| !s)
+}
+
+pub fn spawn_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: MINIMAP_SIZE,
+            height: MINIMAP_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &BACKGROUND,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    let image = images.add(image);
+
+    commands
+        .spawn((
+            MinimapRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                width: Val::Px(MINIMAP_SIZE as f32),
+                height: Val::Px(MINIMAP_SIZE as f32),
+                border: UiRect::all(Val::Px(2.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BorderColor(Color::srgba(0.4, 0.4, 0.4, 0.8)),
+            ImageNode::new(image.clone()),
+            Visibility::Visible,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("N"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(2.0),
+                    ..default()
+                },
+            ));
+        });
+
+    commands.insert_resource(Minimap {
+        image,
+        zoom: DEFAULT_ZOOM,
+        drawn: None,
+    });
+}
+
+pub fn show_hide_minimap(
+    game_state: Res<GameState>,
+    mut query: Query<(&mut Visibility, &mut Node), With<MinimapRoot>>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+    for (mut vis, mut node) in &mut query {
+        if *game_state == GameState::InInventory {
+            *vis = Visibility::Hidden;
+            node.display = Display::None;
+        } else {
+            *vis = Visibility::Visible;
+            node.display = Display::Flex;
+        }
+    }
+}
+
+/// `-` zooms the minimap out and `=` back in.
+pub fn zoom_minimap(
+    keys: Res<ButtonInput<KeyCode>>,
+    game_state: Res<GameState>,
+    mut minimap: ResMut<Minimap>,
+) {
+    if *game_state != GameState::Playing {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        minimap.zoom = (minimap.zoom + 1).min(ZOOM_LEVELS.len() - 1);
+    }
+    if keys.just_pressed(KeyCode::Equal) {
+        minimap.zoom = minimap.zoom.saturating_sub(1);
+    }
+}
+
+/// Marks chunks that were loaded or had a block changed since the last frame, and
+/// forgets the ones that were unloaded.
+pub fn track_minimap_chunks(
+    chunk_map: Res<ChunkMap>,
+    mut tiles: ResMut<MinimapTiles>,
+    mut placed: EventReader<BlockPlacedEvent>,
+    mut removed: EventReader<BlockRemovedEvent>,
+) {
+    let positions = placed
+        .read()
+        .map(|event| event.position)
+        .chain(removed.read().map(|event| event.position));
+    for position in positions {
+        tiles.block_changed(position.x, position.z);
+    }
+
+    if !chunk_map.is_changed() {
+        return;
+    }
+    let mut changed = Vec::new();
+    for &pos in chunk_map.chunks.keys() {
+        if !tiles.tiles.contains_key(&pos) {
+            changed.push(pos);
+        }
+    }
+    tiles.tiles.retain(|pos, _| {
+        let loaded = chunk_map.chunks.contains_key(pos);
+        if !loaded {
+            changed.push(*pos);
+        }
+        loaded
+    });
+    for pos in changed {
+        // The edge of the chunk to the -Z side is shaded against this one
+        tiles.stale.insert(pos);
+        tiles.stale.insert(ChunkPos(pos.0, pos.1 - 1));
+        tiles.changed = true;
+    }
+}
+
+/// Redraws up to `TILES_PER_FRAME` changed chunks, then the minimap itself if any
+/// tile, the zoom or the player's position or heading on it changed.
+pub fn update_minimap(
+    chunk_map: Res<ChunkMap>,
+    mut tiles: ResMut<MinimapTiles>,
+    mut minimap: ResMut<Minimap>,
+    mut images: ResMut<Assets<Image>>,
+    camera_query: Query<(&Transform, &Player), With<FlyCam>>,
+) {
+    let redraw: Vec<ChunkPos> = tiles.stale.iter().take(TILES_PER_FRAME).copied().collect();
+    for pos in redraw {
+        tiles.stale.remove(&pos);
+        if chunk_map.chunks.contains_key(&pos) {
+            let tile = draw_tile(&chunk_map, pos);
+            tiles.tiles.insert(pos, tile);
+            tiles.changed = true;
+        }
+    }
+
+    let Ok((transform, player)) = camera_query.get_single() else {
+        return;
+    };
+    let blocks = ZOOM_LEVELS[minimap.zoom];
+    let center = player.position.xz();
+    // Looking along -Z points down the minimap and along -X to its right
+    let forward = transform.forward().as_vec3();
+    let heading = Vec2::new(-forward.x, -forward.z).normalize_or(Vec2::NEG_Y);
+
+    let drawn = (
+        (center / blocks * MINIMAP_SIZE as f32).floor().as_ivec2(),
+        heading.to_angle().to_degrees().round() as i32,
+        minimap.zoom,
+    );
+    if !tiles.changed && minimap.drawn == Some(drawn) {
+        return;
+    }
+    tiles.changed = false;
+    minimap.drawn = Some(drawn);
+    if let Some(image) = images.get_mut(&minimap.image) {
+        image.data = draw_minimap(&tiles, center, blocks, heading);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block::BlockType;
+    use crate::world::chunk::Chunk;
+
+    fn pixel(pixels: &[u8], x: u32, y: u32) -> [u8; 4] {
+        let i = ((x + y * MINIMAP_SIZE) * 4) as usize;
+        pixels[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn north_is_up_and_changed_blocks_are_redrawn() {
+        let mut chunk_map = ChunkMap::default();
+        chunk_map.chunks.insert(ChunkPos(0, 0), Chunk::new());
+        for x in 0..CHUNK_SIZE as i32 {
+            for z in 0..CHUNK_SIZE as i32 {
+                chunk_map.set_block(x, 10, z, BlockType::Grass);
+            }
+        }
+        chunk_map.set_block(4, 10, 12, BlockType::Sand);
+
+        let mut tiles = MinimapTiles::default();
+        tiles
+            .tiles
+            .insert(ChunkPos(0, 0), draw_tile(&chunk_map, ChunkPos(0, 0)));
+        let sand = tiles.pixel(4, 12).unwrap();
+        assert_ne!(sand, tiles.pixel(5, 12).unwrap());
+
+        // One pixel per block, centered on (8, 8): +Z is up and +X to the left
+        let pixels = draw_minimap(&tiles, Vec2::new(8.0, 8.0), 128.0, Vec2::NEG_Y);
+        assert_eq!(pixel(&pixels, 67, 59), sand);
+        // Outside the loaded chunk
+        assert_eq!(pixel(&pixels, 0, 0), BACKGROUND);
+        // The arrow, pointing up
+        assert_eq!(pixel(&pixels, 64, 59), ARROW_COLOR);
+        assert_ne!(pixel(&pixels, 64, 69), ARROW_COLOR);
+
+        // Only the chunk with the change, and its neighbor across the lit edge
+        tiles.block_changed(4, 16);
+        assert_eq!(tiles.stale, HashSet::from([ChunkPos(0, 1), ChunkPos(0, 0)]));
+    }
+}
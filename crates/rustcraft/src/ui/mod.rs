@@ -1,15 +1,24 @@
 pub mod block_preview;
+pub mod death_screen;
+pub mod health_bar;
 pub mod hotbar;
 pub mod inventory_screen;
+pub mod minimap;
 pub mod pause_menu;
 
 use bevy::prelude::*;
 use block_preview::setup_block_previews;
+use death_screen::{handle_respawn_button, show_hide_death_screen, spawn_death_screen};
+use health_bar::{show_hide_health_bar, spawn_health_bar, update_health_bar, update_hunger_bar};
 use hotbar::{show_hide_hotbar, spawn_hotbar, update_hotbar};
 use inventory_screen::{
     DragState, drag_and_drop, show_hide_inventory_screen, spawn_inventory_screen,
     update_inventory_screen,
 };
+use minimap::{
+    MinimapTiles, show_hide_minimap, spawn_minimap, track_minimap_chunks, update_minimap,
+    zoom_minimap,
+};
 use pause_menu::{
     button_hover, handle_quit_button, handle_resume_button, show_hide_pause_menu, spawn_pause_menu,
 };
@@ -19,13 +28,17 @@ pub struct UiPlugin;
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<DragState>()
+            .init_resource::<MinimapTiles>()
             .add_systems(
                 Startup,
                 (
-                    setup_block_previews,
+                    setup_block_previews.after(crate::render::setup_block_textures),
                     spawn_pause_menu,
                     spawn_hotbar,
                     spawn_inventory_screen,
+                    spawn_minimap,
+                    spawn_health_bar,
+                    spawn_death_screen,
                 ),
             )
             .add_systems(
@@ -40,6 +53,14 @@ impl Plugin for UiPlugin {
                     show_hide_inventory_screen,
                     update_inventory_screen,
                     drag_and_drop,
+                    show_hide_minimap,
+                    zoom_minimap,
+                    (track_minimap_chunks, update_minimap).chain(),
+                    show_hide_health_bar,
+                    update_health_bar,
+                    update_hunger_bar,
+                    show_hide_death_screen,
+                    handle_respawn_button,
                 ),
             );
     }
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
 use crate::player::camera::GameState;
+use crate::ui::death_screen::RespawnButton;
 
 #[derive(Component)]
 pub struct PauseMenuRoot;
@@ -139,17 +140,23 @@ pub fn handle_quit_button(
 
 pub fn button_hover(
     mut query: Query<
-        (&Interaction, &mut BackgroundColor, Option<&ResumeButton>),
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            Has<ResumeButton>,
+            Has<RespawnButton>,
+        ),
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (interaction, mut bg, is_resume) in &mut query {
-        let base = if is_resume.is_some() {
+    for (interaction, mut bg, is_resume, is_respawn) in &mut query {
+        let is_resume = is_resume || is_respawn;
+        let base = if is_resume {
             Color::srgb(0.3, 0.3, 0.3)
         } else {
             Color::srgb(0.5, 0.15, 0.15)
         };
-        let hover = if is_resume.is_some() {
+        let hover = if is_resume {
             Color::srgb(0.4, 0.4, 0.4)
         } else {
             Color::srgb(0.6, 0.2, 0.2)
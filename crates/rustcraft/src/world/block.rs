@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use super::model::BlockModel;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum BlockType {
     #[default]
@@ -11,15 +13,149 @@ pub enum BlockType {
     Water,
     Wood,
     Leaves,
+    StoneSlab,
+    WoodStairs,
+    Fence,
+    Flower,
+    TallGrass,
+    GlassPane,
+    Apple,
+    Bread,
 }
 
 impl BlockType {
+    /// Every block that can be in the world. Food is only ever an item and isn't
+    /// listed.
+    pub const ALL: [BlockType; 14] = [
+        BlockType::Air,
+        BlockType::Grass,
+        BlockType::Dirt,
+        BlockType::Stone,
+        BlockType::Sand,
+        BlockType::Water,
+        BlockType::Wood,
+        BlockType::Leaves,
+        BlockType::StoneSlab,
+        BlockType::WoodStairs,
+        BlockType::Fence,
+        BlockType::Flower,
+        BlockType::TallGrass,
+        BlockType::GlassPane,
+    ];
+
+    /// Something that can be targeted, built against and stood on; not air or a
+    /// fluid.
     pub fn is_solid(self) -> bool {
-        !matches!(self, BlockType::Air)
+        !matches!(self, BlockType::Air | BlockType::Water)
+    }
+
+    /// Swum through rather than walked on.
+    pub fn is_fluid(self) -> bool {
+        matches!(self, BlockType::Water)
+    }
+
+    /// Hunger and saturation restored by eating it, for items that are food
+    /// rather than blocks to place.
+    pub fn food(self) -> Option<(f32, f32)> {
+        match self {
+            BlockType::Apple => Some((4.0, 2.4)),
+            BlockType::Bread => Some((5.0, 6.0)),
+            _ => None,
+        }
+    }
+
+    pub fn is_food(self) -> bool {
+        self.food().is_some()
     }
 
     pub fn is_transparent(self) -> bool {
-        matches!(self, BlockType::Air | BlockType::Water)
+        matches!(
+            self,
+            BlockType::Air | BlockType::Water | BlockType::GlassPane
+        )
+    }
+
+    /// Visible but see-through, drawn in the alpha-blended pass.
+    pub fn is_translucent(self) -> bool {
+        matches!(self, BlockType::Water | BlockType::GlassPane)
+    }
+
+    pub fn model(self) -> BlockModel {
+        match self {
+            BlockType::Air => BlockModel::Empty,
+            BlockType::StoneSlab => BlockModel::Slab,
+            BlockType::WoodStairs => BlockModel::Stairs,
+            BlockType::Fence => BlockModel::Fence,
+            BlockType::Flower | BlockType::TallGrass => BlockModel::Cross,
+            // Held and dropped as flat sprites, like plants
+            BlockType::Apple | BlockType::Bread => BlockModel::Cross,
+            BlockType::GlassPane => BlockModel::Pane,
+            _ => BlockModel::Cube,
+        }
+    }
+
+    /// Opaque and filling its whole cell, so it hides every face against it and
+    /// shades the corners next to it.
+    pub fn is_opaque_cube(self) -> bool {
+        self.model() == BlockModel::Cube && !self.is_transparent()
+    }
+
+    /// Whether a neighbor's face against the side of this block towards `side` is
+    /// hidden by it.
+    pub fn hides_face(self, side: IVec3) -> bool {
+        !self.is_transparent() && self.model().covers(side)
+    }
+
+    /// The block whose textures this one is drawn with.
+    pub fn texture_source(self) -> BlockType {
+        match self {
+            BlockType::StoneSlab => BlockType::Stone,
+            BlockType::WoodStairs | BlockType::Fence => BlockType::Wood,
+            _ => self,
+        }
+    }
+
+    /// Seconds of holding the break button it takes to mine the block in survival.
+    pub fn break_time(self) -> f32 {
+        match self {
+            BlockType::Air | BlockType::Water => 0.0,
+            BlockType::Flower | BlockType::TallGrass => 0.0,
+            BlockType::Apple | BlockType::Bread => 0.0,
+            BlockType::Leaves | BlockType::GlassPane => 0.3,
+            BlockType::Dirt | BlockType::Sand => 0.6,
+            BlockType::Grass => 0.7,
+            BlockType::Stone | BlockType::StoneSlab => 1.5,
+            BlockType::Wood | BlockType::WoodStairs | BlockType::Fence => 2.0,
+        }
+    }
+
+    /// The block's lowercase name, e.g. `"stone"` or `"tall_grass"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            BlockType::Air => "air",
+            BlockType::Grass => "grass",
+            BlockType::Dirt => "dirt",
+            BlockType::Stone => "stone",
+            BlockType::Sand => "sand",
+            BlockType::Water => "water",
+            BlockType::Wood => "wood",
+            BlockType::Leaves => "leaves",
+            BlockType::StoneSlab => "stone_slab",
+            BlockType::WoodStairs => "wood_stairs",
+            BlockType::Fence => "fence",
+            BlockType::Flower => "flower",
+            BlockType::TallGrass => "tall_grass",
+            BlockType::GlassPane => "glass_pane",
+            BlockType::Apple => "apple",
+            BlockType::Bread => "bread",
+        }
+    }
+
+    /// Looks up a block in `ALL` by its lowercase name, e.g. `"stone"`.
+    pub fn from_name(name: &str) -> Option<BlockType> {
+        BlockType::ALL
+            .into_iter()
+            .find(|block| block.name() == name)
     }
 
     pub fn color(self) -> Color {
@@ -32,6 +168,13 @@ impl BlockType {
             BlockType::Water => Color::srgba(0.20, 0.40, 0.80, 0.60),
             BlockType::Wood => Color::srgb(0.40, 0.26, 0.13),
             BlockType::Leaves => Color::srgb(0.18, 0.55, 0.18),
+            BlockType::StoneSlab => BlockType::Stone.color(),
+            BlockType::WoodStairs | BlockType::Fence => BlockType::Wood.color(),
+            BlockType::Flower => Color::srgb(0.85, 0.2, 0.25),
+            BlockType::TallGrass => Color::srgb(0.38, 0.66, 0.26),
+            BlockType::GlassPane => Color::srgba(0.75, 0.88, 0.95, 0.35),
+            BlockType::Apple => Color::srgb(0.80, 0.12, 0.10),
+            BlockType::Bread => Color::srgb(0.80, 0.58, 0.28),
         }
     }
 }
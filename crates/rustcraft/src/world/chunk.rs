@@ -10,6 +10,7 @@ pub const BLOCKS_PER_CHUNK: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_HEIGHT;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
 pub struct ChunkPos(pub i32, pub i32);
 
+#[derive(Clone)]
 pub struct Chunk {
     pub blocks: Vec<BlockType>,
     pub dirty: bool,
@@ -0,0 +1,213 @@
+use std::fmt;
+
+use super::block::BlockType;
+use super::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, Chunk, ChunkPos};
+use super::generation::WorldGenerator;
+
+pub const DEFAULT_PRESET: &str = "1*stone,3*dirt,1*grass";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresetError {
+    Empty,
+    EmptyLayer(usize),
+    InvalidCount { layer: usize, count: String },
+    UnknownBlock { layer: usize, name: String },
+    TooTall(usize),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Empty => write!(f, "preset has no layers"),
+            PresetError::EmptyLayer(layer) => write!(f, "layer {} is empty", layer + 1),
+            PresetError::InvalidCount { layer, count } => write!(
+                f,
+                "layer {} has invalid count {:?} (expected a positive integer)",
+                layer + 1,
+                count
+            ),
+            PresetError::UnknownBlock { layer, name } => {
+                write!(f, "layer {} has unknown block {:?}", layer + 1, name)
+            }
+            PresetError::TooTall(height) => write!(
+                f,
+                "layers are {} blocks tall but a chunk is only {} blocks high",
+                height, CHUNK_HEIGHT
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+/// A perfectly flat world made of horizontal layers, listed bottom to top.
+pub struct FlatGenerator {
+    pub layers: Vec<(BlockType, usize)>,
+}
+
+impl FlatGenerator {
+    /// Parses a preset such as `"1*stone,3*dirt,1*grass"`. A layer without a
+    /// count (`"grass"`) is one block thick.
+    pub fn from_preset(preset: &str) -> Result<Self, PresetError> {
+        if preset.trim().is_empty() {
+            return Err(PresetError::Empty);
+        }
+
+        let mut layers = Vec::new();
+        for (i, layer) in preset.split(',').enumerate() {
+            let layer = layer.trim();
+            if layer.is_empty() {
+                return Err(PresetError::EmptyLayer(i));
+            }
+
+            let (count, name) = match layer.split_once('*') {
+                Some((count, name)) => {
+                    let count = count.trim();
+                    match count.parse::<usize>() {
+                        Ok(n) if n > CHUNK_HEIGHT => return Err(PresetError::TooTall(n)),
+                        Ok(n) if n > 0 => (n, name.trim()),
+                        _ => {
+                            return Err(PresetError::InvalidCount {
+                                layer: i,
+                                count: count.to_string(),
+                            });
+                        }
+                    }
+                }
+                None => (1, layer),
+            };
+
+            let block = BlockType::from_name(&name.to_ascii_lowercase()).ok_or_else(|| {
+                PresetError::UnknownBlock {
+                    layer: i,
+                    name: name.to_string(),
+                }
+            })?;
+            layers.push((block, count));
+        }
+
+        let height = layers
+            .iter()
+            .try_fold(0usize, |height, &(_, count)| height.checked_add(count))
+            .ok_or(PresetError::TooTall(usize::MAX))?;
+        if height > CHUNK_HEIGHT {
+            return Err(PresetError::TooTall(height));
+        }
+
+        Ok(Self { layers })
+    }
+}
+
+impl WorldGenerator for FlatGenerator {
+    fn generate_chunk(&self, _pos: ChunkPos, _seed: u32) -> Chunk {
+        let mut chunk = Chunk::new();
+        let mut y = 0;
+
+        for &(block, count) in &self.layers {
+            for _ in 0..count {
+                for lx in 0..CHUNK_SIZE {
+                    for lz in 0..CHUNK_SIZE {
+                        chunk.set_block(lx, y, lz, block);
+                    }
+                }
+                y += 1;
+            }
+        }
+
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_default_preset() {
+        let generator = FlatGenerator::from_preset(DEFAULT_PRESET).unwrap();
+        assert_eq!(
+            generator.layers,
+            vec![
+                (BlockType::Stone, 1),
+                (BlockType::Dirt, 3),
+                (BlockType::Grass, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn count_defaults_to_one_and_whitespace_is_ignored() {
+        let generator = FlatGenerator::from_preset(" 2 * Stone , sand ").unwrap();
+        assert_eq!(
+            generator.layers,
+            vec![(BlockType::Stone, 2), (BlockType::Sand, 1)]
+        );
+    }
+
+    #[test]
+    fn reports_preset_errors() {
+        assert_eq!(
+            FlatGenerator::from_preset("").err(),
+            Some(PresetError::Empty)
+        );
+        assert_eq!(
+            FlatGenerator::from_preset("1*stone,,1*grass").err(),
+            Some(PresetError::EmptyLayer(1))
+        );
+        assert_eq!(
+            FlatGenerator::from_preset("x*stone").err(),
+            Some(PresetError::InvalidCount {
+                layer: 0,
+                count: "x".to_string()
+            })
+        );
+        assert_eq!(
+            FlatGenerator::from_preset("0*stone").err(),
+            Some(PresetError::InvalidCount {
+                layer: 0,
+                count: "0".to_string()
+            })
+        );
+        assert_eq!(
+            FlatGenerator::from_preset("1*stone,2*bedrock").err(),
+            Some(PresetError::UnknownBlock {
+                layer: 1,
+                name: "bedrock".to_string()
+            })
+        );
+        assert_eq!(
+            FlatGenerator::from_preset("3*apple").err(),
+            Some(PresetError::UnknownBlock {
+                layer: 0,
+                name: "apple".to_string()
+            })
+        );
+        assert_eq!(
+            FlatGenerator::from_preset("40*stone,40*dirt").err(),
+            Some(PresetError::TooTall(80))
+        );
+        assert_eq!(
+            FlatGenerator::from_preset(&format!("{}*stone,2*dirt", usize::MAX)).err(),
+            Some(PresetError::TooTall(usize::MAX))
+        );
+    }
+
+    #[test]
+    fn fills_every_column_with_the_layers() {
+        let generator = FlatGenerator::from_preset(DEFAULT_PRESET).unwrap();
+        let chunk = generator.generate_chunk(ChunkPos(-3, 7), 0);
+
+        for lx in 0..CHUNK_SIZE {
+            for lz in 0..CHUNK_SIZE {
+                assert_eq!(chunk.get_block(lx, 0, lz), BlockType::Stone);
+                for y in 1..4 {
+                    assert_eq!(chunk.get_block(lx, y, lz), BlockType::Dirt);
+                }
+                assert_eq!(chunk.get_block(lx, 4, lz), BlockType::Grass);
+                for y in 5..CHUNK_HEIGHT {
+                    assert_eq!(chunk.get_block(lx, y, lz), BlockType::Air);
+                }
+            }
+        }
+    }
+}
@@ -1,51 +1,228 @@
+use bevy::prelude::*;
 use noise::{NoiseFn, Perlin};
 
 use super::block::BlockType;
 use super::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, Chunk, ChunkMap, ChunkPos};
+use super::pipeline::{GenRegion, GenerationPipeline};
+use super::structure::{StructureIndex, WithStructures};
 
-const WORLD_CHUNKS: i32 = 8;
+/// Chunks generated along each side of the world at startup.
+pub const WORLD_CHUNKS: i32 = 8;
 const BASE_HEIGHT: f64 = 20.0;
 const AMPLITUDE: f64 = 15.0;
 const NOISE_SCALE: f64 = 0.02;
 const SAND_LEVEL: i32 = 14;
-const SEED: u32 = 42;
+const TREE_RARITY: u32 = 97;
+/// Out of 64 grass columns, how many grow tall grass and how many a flower.
+const TALL_GRASS_CHANCE: u32 = 6;
+const FLOWER_CHANCE: u32 = 1;
+const PLANT_SALT: u32 = 0x3c6e_f372;
+pub const DEFAULT_SEED: u32 = 42;
 
-pub fn generate_world(mut chunk_map: bevy::prelude::ResMut<ChunkMap>) {
-    let perlin = Perlin::new(SEED);
+/// Produces the blocks of a world, one chunk and one `GenerationStage` at a time.
+/// Implementations must be deterministic for a given `(pos, seed)` so chunks can be
+/// regenerated independently.
+///
+/// `generate_chunk` is the terrain stage and sees only its own chunk. The other
+/// stages run once every neighbor has finished the previous stage and may read and
+/// write the surrounding chunks through the `GenRegion`.
+pub trait WorldGenerator: Send + Sync + 'static {
+    fn generate_chunk(&self, pos: ChunkPos, seed: u32) -> Chunk;
+    fn carve(&self, _region: &mut GenRegion, _seed: u32) {}
+    fn surface(&self, _region: &mut GenRegion, _seed: u32) {}
+    fn decorate(&self, _region: &mut GenRegion, _seed: u32) {}
+    fn light(&self, _region: &mut GenRegion, _seed: u32) {}
+}
 
-    for cx in 0..WORLD_CHUNKS {
-        for cz in 0..WORLD_CHUNKS {
-            let mut chunk = Chunk::new();
-
-            for lx in 0..CHUNK_SIZE {
-                for lz in 0..CHUNK_SIZE {
-                    let wx = cx as f64 * CHUNK_SIZE as f64 + lx as f64;
-                    let wz = cz as f64 * CHUNK_SIZE as f64 + lz as f64;
-
-                    let noise_val = perlin.get([wx * NOISE_SCALE, wz * NOISE_SCALE]);
-                    let height = (BASE_HEIGHT + noise_val * AMPLITUDE) as i32;
-                    let height = height.clamp(1, CHUNK_HEIGHT as i32 - 1);
-
-                    for y in 0..=height {
-                        let block = if y == height {
-                            if height <= SAND_LEVEL {
-                                BlockType::Sand
-                            } else {
-                                BlockType::Grass
-                            }
-                        } else if y >= height - 3 {
-                            BlockType::Dirt
-                        } else {
-                            BlockType::Stone
-                        };
-
-                        chunk.set_block(lx, y as usize, lz, block);
-                    }
+/// The generator and seed used to fill the `ChunkMap` of the current world.
+#[derive(Resource)]
+pub struct WorldGen {
+    pub generator: Box<dyn WorldGenerator>,
+    pub seed: u32,
+}
+
+impl WorldGen {
+    pub fn new(generator: impl WorldGenerator, seed: u32) -> Self {
+        Self {
+            generator: Box::new(generator),
+            seed,
+        }
+    }
+}
+
+impl Default for WorldGen {
+    fn default() -> Self {
+        Self::new(WithStructures(PerlinGenerator), DEFAULT_SEED)
+    }
+}
+
+/// Rolling Perlin-noise terrain: stone, a few layers of dirt, grass or sand on top,
+/// and scattered trees.
+pub struct PerlinGenerator;
+
+impl WorldGenerator for PerlinGenerator {
+    fn generate_chunk(&self, pos: ChunkPos, seed: u32) -> Chunk {
+        let perlin = Perlin::new(seed);
+        let mut chunk = Chunk::new();
+
+        for lx in 0..CHUNK_SIZE {
+            for lz in 0..CHUNK_SIZE {
+                let wx = pos.0 as f64 * CHUNK_SIZE as f64 + lx as f64;
+                let wz = pos.1 as f64 * CHUNK_SIZE as f64 + lz as f64;
+
+                let noise_val = perlin.get([wx * NOISE_SCALE, wz * NOISE_SCALE]);
+                let height = (BASE_HEIGHT + noise_val * AMPLITUDE) as i32;
+                let height = height.clamp(1, CHUNK_HEIGHT as i32 - 1);
+
+                for y in 0..=height {
+                    chunk.set_block(lx, y as usize, lz, BlockType::Stone);
                 }
             }
+        }
+
+        chunk
+    }
+
+    fn surface(&self, region: &mut GenRegion, _seed: u32) {
+        let chunk = region.chunk_mut();
+
+        for lx in 0..CHUNK_SIZE {
+            for lz in 0..CHUNK_SIZE {
+                cover_column(chunk, lx, lz, None);
+            }
+        }
+    }
 
-            chunk.dirty = false;
-            chunk_map.chunks.insert(ChunkPos(cx, cz), chunk);
+    fn decorate(&self, region: &mut GenRegion, seed: u32) {
+        let pos = region.center();
+
+        for lx in 0..CHUNK_SIZE {
+            for lz in 0..CHUNK_SIZE {
+                let wx = pos.0 * CHUNK_SIZE as i32 + lx as i32;
+                let wz = pos.1 * CHUNK_SIZE as i32 + lz as i32;
+
+                let hash = column_hash(wx, wz, seed);
+                if !hash.is_multiple_of(TREE_RARITY) {
+                    continue;
+                }
+                let Some(ground) = surface_height(region.chunk_mut(), lx, lz) else {
+                    continue;
+                };
+                if region.get_block(wx, ground as i32, wz) != BlockType::Grass {
+                    continue;
+                }
+
+                let trunk_height = 4 + (hash / TREE_RARITY % 2) as i32;
+                place_tree(region, wx, ground as i32 + 1, wz, trunk_height);
+            }
+        }
+
+        // Plants go in after the trees so trunks don't grow out of them
+        for lx in 0..CHUNK_SIZE {
+            for lz in 0..CHUNK_SIZE {
+                let wx = pos.0 * CHUNK_SIZE as i32 + lx as i32;
+                let wz = pos.1 * CHUNK_SIZE as i32 + lz as i32;
+
+                let roll = column_hash(wx, wz, seed ^ PLANT_SALT) % 64;
+                let plant = if roll < FLOWER_CHANCE {
+                    BlockType::Flower
+                } else if roll < FLOWER_CHANCE + TALL_GRASS_CHANCE {
+                    BlockType::TallGrass
+                } else {
+                    continue;
+                };
+                let Some(ground) = surface_height(region.chunk_mut(), lx, lz) else {
+                    continue;
+                };
+                let ground = ground as i32;
+                if region.get_block(wx, ground, wz) == BlockType::Grass
+                    && region.get_block(wx, ground + 1, wz) == BlockType::Air
+                {
+                    region.set_block(wx, ground + 1, wz, plant);
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the top of a column with its surface layers: `top` over three blocks of
+/// dirt (for grass) or more of the same block, or when `top` is `None`, grass over
+/// dirt with sand near sea level.
+pub fn cover_column(chunk: &mut Chunk, lx: usize, lz: usize, top: Option<BlockType>) {
+    let Some(height) = surface_height(chunk, lx, lz) else {
+        return;
+    };
+
+    let (top, under) = match top {
+        Some(BlockType::Grass) => (BlockType::Grass, BlockType::Dirt),
+        Some(block) => (block, block),
+        None if height as i32 <= SAND_LEVEL => (BlockType::Sand, BlockType::Dirt),
+        None => (BlockType::Grass, BlockType::Dirt),
+    };
+
+    for y in height.saturating_sub(3)..height {
+        chunk.set_block(lx, y, lz, under);
+    }
+    chunk.set_block(lx, height, lz, top);
+}
+
+/// The y of the highest non-air block in a column.
+fn surface_height(chunk: &Chunk, lx: usize, lz: usize) -> Option<usize> {
+    (0..CHUNK_HEIGHT)
+        .rev()
+        .find(|&y| chunk.get_block(lx, y, lz) != BlockType::Air)
+}
+
+/// Places a trunk starting at `(wx, base_y, wz)` topped with a leaf canopy that can
+/// reach two blocks into neighboring chunks.
+fn place_tree(region: &mut GenRegion, wx: i32, base_y: i32, wz: i32, trunk_height: i32) {
+    let top = base_y + trunk_height;
+    if top + 1 >= CHUNK_HEIGHT as i32 {
+        return;
+    }
+
+    for y in top - 2..=top + 1 {
+        let radius: i32 = if y >= top { 1 } else { 2 };
+        for dx in -radius..=radius {
+            for dz in -radius..=radius {
+                // Trim the corners of the wide layers
+                if radius == 2 && dx.abs() == 2 && dz.abs() == 2 {
+                    continue;
+                }
+                if region.get_block(wx + dx, y, wz + dz) == BlockType::Air {
+                    region.set_block(wx + dx, y, wz + dz, BlockType::Leaves);
+                }
+            }
+        }
+    }
+
+    for y in base_y..top {
+        region.set_block(wx, y, wz, BlockType::Wood);
+    }
+}
+
+/// Deterministic per-column hash used to scatter features.
+pub fn column_hash(wx: i32, wz: i32, seed: u32) -> u32 {
+    let mut h = (wx as u32).wrapping_mul(0x27d4_eb2d)
+        ^ (wz as u32).wrapping_mul(0x1656_67b1)
+        ^ seed.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+pub fn generate_world(
+    world_gen: Res<WorldGen>,
+    mut pipeline: ResMut<GenerationPipeline>,
+    mut chunk_map: ResMut<ChunkMap>,
+    mut structures: ResMut<StructureIndex>,
+) {
+    for cx in 0..WORLD_CHUNKS {
+        for cz in 0..WORLD_CHUNKS {
+            pipeline.generate(ChunkPos(cx, cz), &world_gen, &mut chunk_map);
         }
     }
+    structures.structures.append(&mut pipeline.structures);
 }
@@ -0,0 +1,290 @@
+use bevy::prelude::*;
+use image::DynamicImage;
+use std::fmt;
+use std::path::Path;
+
+use super::block::BlockType;
+use super::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, Chunk, ChunkPos};
+use super::generation::{WorldGenerator, cover_column};
+use super::pipeline::GenRegion;
+
+const DEFAULT_VERTICAL_SCALE: f32 = 48.0;
+const DEFAULT_BASE_HEIGHT: i32 = 1;
+
+/// Blocks a material map pixel can select, matched by their `BlockType::color`.
+const MATERIAL_KEYS: [BlockType; 7] = [
+    BlockType::Grass,
+    BlockType::Dirt,
+    BlockType::Stone,
+    BlockType::Sand,
+    BlockType::Water,
+    BlockType::Wood,
+    BlockType::Leaves,
+];
+
+#[derive(Debug)]
+pub enum HeightmapError {
+    Image(image::ImageError),
+    SizeMismatch {
+        heightmap: (u32, u32),
+        materials: (u32, u32),
+    },
+}
+
+impl fmt::Display for HeightmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeightmapError::Image(err) => write!(f, "{}", err),
+            HeightmapError::SizeMismatch {
+                heightmap,
+                materials,
+            } => write!(
+                f,
+                "material map is {}x{} but the heightmap is {}x{}",
+                materials.0, materials.1, heightmap.0, heightmap.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HeightmapError {}
+
+impl From<image::ImageError> for HeightmapError {
+    fn from(err: image::ImageError) -> Self {
+        HeightmapError::Image(err)
+    }
+}
+
+/// What the generator samples for columns outside the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeMode {
+    /// Repeat the nearest edge pixel.
+    #[default]
+    Clamp,
+    /// Repeat the whole image.
+    Tile,
+}
+
+/// Terrain read from a grayscale image: black is `base_height`, white is
+/// `base_height + vertical_scale`. Pixel (0, 0) sits at world column `origin`
+/// and each pixel is one block.
+pub struct HeightmapGenerator {
+    width: u32,
+    height: u32,
+    heights: Vec<f32>,
+    materials: Option<Vec<BlockType>>,
+    vertical_scale: f32,
+    base_height: i32,
+    origin: IVec2,
+    edge_mode: EdgeMode,
+}
+
+impl HeightmapGenerator {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HeightmapError> {
+        Ok(Self::from_image(&image::open(path)?))
+    }
+
+    pub fn from_image(heightmap: &DynamicImage) -> Self {
+        let gray = heightmap.to_luma16();
+        Self {
+            width: gray.width(),
+            height: gray.height(),
+            heights: gray
+                .pixels()
+                .map(|p| p.0[0] as f32 / u16::MAX as f32)
+                .collect(),
+            materials: None,
+            vertical_scale: DEFAULT_VERTICAL_SCALE,
+            base_height: DEFAULT_BASE_HEIGHT,
+            origin: IVec2::ZERO,
+            edge_mode: EdgeMode::Clamp,
+        }
+    }
+
+    /// Loads a color-keyed map choosing each column's surface block. Pixels pick the
+    /// block whose `color()` is closest.
+    pub fn with_material_map(self, path: impl AsRef<Path>) -> Result<Self, HeightmapError> {
+        let materials = image::open(path)?;
+        self.with_material_image(&materials)
+    }
+
+    pub fn with_material_image(mut self, materials: &DynamicImage) -> Result<Self, HeightmapError> {
+        let rgb = materials.to_rgb8();
+        if rgb.dimensions() != (self.width, self.height) {
+            return Err(HeightmapError::SizeMismatch {
+                heightmap: (self.width, self.height),
+                materials: rgb.dimensions(),
+            });
+        }
+        self.materials = Some(rgb.pixels().map(|p| nearest_material(p.0)).collect());
+        Ok(self)
+    }
+
+    pub fn with_vertical_scale(mut self, vertical_scale: f32) -> Self {
+        self.vertical_scale = vertical_scale;
+        self
+    }
+
+    pub fn with_origin(mut self, origin: IVec2) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_edge_mode(mut self, edge_mode: EdgeMode) -> Self {
+        self.edge_mode = edge_mode;
+        self
+    }
+
+    /// Index of the pixel covering world column `(wx, wz)`.
+    fn pixel_index(&self, wx: i32, wz: i32) -> usize {
+        let px = wx - self.origin.x;
+        let pz = wz - self.origin.y;
+        let (px, pz) = match self.edge_mode {
+            EdgeMode::Clamp => (
+                px.clamp(0, self.width as i32 - 1),
+                pz.clamp(0, self.height as i32 - 1),
+            ),
+            EdgeMode::Tile => (
+                px.rem_euclid(self.width as i32),
+                pz.rem_euclid(self.height as i32),
+            ),
+        };
+        px as usize + pz as usize * self.width as usize
+    }
+
+    fn column_height(&self, wx: i32, wz: i32) -> i32 {
+        let value = self.heights[self.pixel_index(wx, wz)];
+        let height = self.base_height + (value * self.vertical_scale).round() as i32;
+        height.clamp(0, CHUNK_HEIGHT as i32 - 1)
+    }
+}
+
+impl WorldGenerator for HeightmapGenerator {
+    fn generate_chunk(&self, pos: ChunkPos, _seed: u32) -> Chunk {
+        let mut chunk = Chunk::new();
+
+        for lx in 0..CHUNK_SIZE {
+            for lz in 0..CHUNK_SIZE {
+                let wx = pos.0 * CHUNK_SIZE as i32 + lx as i32;
+                let wz = pos.1 * CHUNK_SIZE as i32 + lz as i32;
+                let height = self.column_height(wx, wz);
+
+                for y in 0..=height {
+                    chunk.set_block(lx, y as usize, lz, BlockType::Stone);
+                }
+            }
+        }
+
+        chunk
+    }
+
+    fn surface(&self, region: &mut GenRegion, _seed: u32) {
+        let pos = region.center();
+        let chunk = region.chunk_mut();
+
+        for lx in 0..CHUNK_SIZE {
+            for lz in 0..CHUNK_SIZE {
+                let wx = pos.0 * CHUNK_SIZE as i32 + lx as i32;
+                let wz = pos.1 * CHUNK_SIZE as i32 + lz as i32;
+                let top = self
+                    .materials
+                    .as_ref()
+                    .map(|materials| materials[self.pixel_index(wx, wz)]);
+                cover_column(chunk, lx, lz, top);
+            }
+        }
+    }
+}
+
+fn nearest_material(rgb: [u8; 3]) -> BlockType {
+    let distance = |block: BlockType| {
+        let key = block.color().to_srgba();
+        let dr = key.red - rgb[0] as f32 / 255.0;
+        let dg = key.green - rgb[1] as f32 / 255.0;
+        let db = key.blue - rgb[2] as f32 / 255.0;
+        dr * dr + dg * dg + db * db
+    };
+    MATERIAL_KEYS
+        .into_iter()
+        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+        .unwrap_or(BlockType::Grass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::chunk::ChunkMap;
+    use crate::world::generation::WorldGen;
+    use crate::world::pipeline::GenerationPipeline;
+    use image::{GrayImage, Luma, Rgb, RgbImage};
+
+    /// A 2x1 image: black on the left, white on the right.
+    fn two_pixel_heightmap() -> DynamicImage {
+        let mut img = GrayImage::new(2, 1);
+        img.put_pixel(0, 0, Luma([0]));
+        img.put_pixel(1, 0, Luma([255]));
+        DynamicImage::ImageLuma8(img)
+    }
+
+    fn top_block(chunk_map: &ChunkMap, wx: i32, wz: i32) -> (i32, BlockType) {
+        (0..CHUNK_HEIGHT as i32)
+            .rev()
+            .map(|y| (y, chunk_map.get_block(wx, y, wz)))
+            .find(|(_, block)| *block != BlockType::Air)
+            .unwrap()
+    }
+
+    fn generate(generator: HeightmapGenerator) -> ChunkMap {
+        let world_gen = WorldGen::new(generator, 0);
+        let mut pipeline = GenerationPipeline::default();
+        let mut chunk_map = ChunkMap::default();
+        pipeline.generate(ChunkPos(0, 0), &world_gen, &mut chunk_map);
+        chunk_map
+    }
+
+    #[test]
+    fn clamps_or_tiles_outside_the_image() {
+        let clamped = generate(
+            HeightmapGenerator::from_image(&two_pixel_heightmap()).with_vertical_scale(20.0),
+        );
+        assert_eq!(top_block(&clamped, 0, 0), (1, BlockType::Sand));
+        assert_eq!(top_block(&clamped, 1, 0), (21, BlockType::Grass));
+        assert_eq!(top_block(&clamped, 5, 9).0, 21);
+
+        let tiled = generate(
+            HeightmapGenerator::from_image(&two_pixel_heightmap())
+                .with_vertical_scale(20.0)
+                .with_origin(IVec2::new(1, 0))
+                .with_edge_mode(EdgeMode::Tile),
+        );
+        assert_eq!(top_block(&tiled, 1, 0).0, 1);
+        assert_eq!(top_block(&tiled, 2, 0).0, 21);
+        assert_eq!(top_block(&tiled, 3, 4).0, 1);
+        assert_eq!(tiled.get_block(2, 18, 0), BlockType::Dirt);
+        assert_eq!(tiled.get_block(2, 17, 0), BlockType::Stone);
+    }
+
+    #[test]
+    fn material_map_picks_the_surface_block() {
+        let mut materials = RgbImage::new(2, 1);
+        materials.put_pixel(0, 0, Rgb([130, 130, 125]));
+        materials.put_pixel(1, 0, Rgb([50, 100, 210]));
+        let chunk_map = generate(
+            HeightmapGenerator::from_image(&two_pixel_heightmap())
+                .with_vertical_scale(20.0)
+                .with_material_image(&DynamicImage::ImageRgb8(materials))
+                .unwrap(),
+        );
+
+        assert_eq!(top_block(&chunk_map, 0, 0), (1, BlockType::Stone));
+        assert_eq!(top_block(&chunk_map, 1, 0), (21, BlockType::Water));
+        assert_eq!(chunk_map.get_block(1, 18, 0), BlockType::Water);
+
+        let mismatched = HeightmapGenerator::from_image(&two_pixel_heightmap())
+            .with_material_image(&DynamicImage::ImageRgb8(RgbImage::new(3, 3)));
+        assert!(matches!(
+            mismatched,
+            Err(HeightmapError::SizeMismatch { .. })
+        ));
+    }
+}
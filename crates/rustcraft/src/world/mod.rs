@@ -1,16 +1,69 @@
 pub mod block;
 pub mod chunk;
+pub mod flat;
 pub mod generation;
+pub mod heightmap;
+pub mod model;
+pub mod pipeline;
+pub mod structure;
+pub mod time;
 
 use bevy::prelude::*;
 use chunk::ChunkMap;
-use generation::generate_world;
+use generation::{WorldGen, generate_world};
+use pipeline::GenerationPipeline;
+use std::path::PathBuf;
+use structure::StructureIndex;
+use time::WorldTime;
 
-pub struct WorldPlugin;
+/// Directory the current world's state is saved in. Without one nothing is saved.
+#[derive(Resource)]
+pub struct WorldDir(pub PathBuf);
+
+pub struct WorldPlugin {
+    world_gen: std::sync::Mutex<Option<WorldGen>>,
+    save_dir: Option<PathBuf>,
+}
+
+impl WorldPlugin {
+    pub fn new() -> Self {
+        Self {
+            world_gen: std::sync::Mutex::new(Some(WorldGen::default())),
+            save_dir: None,
+        }
+    }
+
+    /// Use `world_gen` unless a `WorldGen` resource was already inserted for this world.
+    pub fn with_world_gen(self, world_gen: WorldGen) -> Self {
+        *self.world_gen.lock().unwrap() = Some(world_gen);
+        self
+    }
+
+    /// Load the world's saved state (currently its time) from `dir` and save it back there on exit.
+    pub fn with_save_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.save_dir = Some(dir.into());
+        self
+    }
+}
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
+        if let Some(world_gen) = self.world_gen.lock().unwrap().take()
+            && !app.world().contains_resource::<WorldGen>()
+        {
+            app.insert_resource(world_gen);
+        }
+
+        if let Some(dir) = &self.save_dir {
+            app.insert_resource(WorldDir(dir.clone()));
+        }
+
         app.init_resource::<ChunkMap>()
-            .add_systems(Startup, generate_world);
+            .init_resource::<GenerationPipeline>()
+            .init_resource::<StructureIndex>()
+            .init_resource::<WorldTime>()
+            .add_systems(Startup, (generate_world, time::load_world_time))
+            .add_systems(Update, time::advance_world_time)
+            .add_systems(Last, time::save_world_time);
     }
 }
@@ -0,0 +1,339 @@
+use bevy::prelude::*;
+
+use super::block::BlockType;
+use super::chunk::ChunkMap;
+
+/// The shape a block is drawn and collided with, declared per block by
+/// `BlockType::model`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockModel {
+    /// Nothing to draw or collide with.
+    Empty,
+    /// Fills its whole cell.
+    Cube,
+    /// The lower half of the cell.
+    Slab,
+    /// A slab with a full-height step on one half, rising towards a neighboring full
+    /// block, or north (+Z) when there is none.
+    Stairs,
+    /// A post joined to neighboring fences and full blocks by two rails. It blocks
+    /// half a block higher than it is drawn so it can't be jumped over.
+    Fence,
+    /// Two quads crossing through the middle of the cell, for plants. Nothing
+    /// collides with it.
+    Cross,
+    /// A thin upright sheet joined to neighboring panes and full blocks. Runs east to
+    /// west when it has nothing to join.
+    Pane,
+}
+
+/// The four horizontal neighbors in the order models take them: north (+Z), south
+/// (-Z), east (+X) and west (-X).
+pub const HORIZONTAL: [IVec3; 4] = [IVec3::Z, IVec3::NEG_Z, IVec3::X, IVec3::NEG_X];
+
+const FENCE_POST: f32 = 0.125;
+const FENCE_RAIL: f32 = 0.0625;
+const FENCE_RAILS: [(f32, f32); 2] = [(0.375, 0.5625), (0.75, 0.9375)];
+const FENCE_COLLISION_HEIGHT: f32 = 1.5;
+const PANE_HALF_THICKNESS: f32 = 0.0625;
+
+/// An axis-aligned box in blocks. Model boxes lie within the unit cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl ModelBox {
+    pub const FULL: ModelBox = ModelBox::new(Vec3::ZERO, Vec3::ONE);
+
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn translate(self, offset: Vec3) -> Self {
+        Self::new(self.min + offset, self.max + offset)
+    }
+
+    /// Whether the boxes share some volume; boxes that only touch don't.
+    pub fn intersects(&self, other: &ModelBox) -> bool {
+        self.min.cmplt(other.max).all() && self.max.cmpgt(other.min).all()
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.min.cmple(point).all() && point.cmplt(self.max).all()
+    }
+}
+
+/// A box centered in the cell horizontally, `half_width` across on each side.
+fn post(half_width: f32, bottom: f32, top: f32) -> ModelBox {
+    ModelBox::new(
+        Vec3::new(0.5 - half_width, bottom, 0.5 - half_width),
+        Vec3::new(0.5 + half_width, top, 0.5 + half_width),
+    )
+}
+
+/// Stretches `model_box` to the side of the cell towards `dir`.
+fn reach(mut model_box: ModelBox, dir: IVec3) -> ModelBox {
+    for axis in [0, 2] {
+        match dir[axis] {
+            1 => model_box.max[axis] = 1.0,
+            -1 => model_box.min[axis] = 0.0,
+            _ => {}
+        }
+    }
+    model_box
+}
+
+/// A bar from the middle of the cell to its side towards `dir`, `half_width` across.
+fn arm(dir: IVec3, half_width: f32, bottom: f32, top: f32) -> ModelBox {
+    reach(post(half_width, bottom, top), dir)
+}
+
+impl BlockModel {
+    /// Whether the model joins a `neighbor` beside it (fences and panes only).
+    pub fn connects_to(self, neighbor: BlockType) -> bool {
+        match self {
+            BlockModel::Fence | BlockModel::Pane => {
+                neighbor.model() == self || neighbor.is_opaque_cube()
+            }
+            _ => false,
+        }
+    }
+
+    /// The boxes the model is drawn with, given the blocks beside it in `HORIZONTAL`
+    /// order. A `Cross` isn't made of boxes and has none.
+    pub fn boxes(self, neighbors: [BlockType; 4]) -> Vec<ModelBox> {
+        let connected = || {
+            HORIZONTAL
+                .into_iter()
+                .zip(neighbors)
+                .filter(move |(_, neighbor)| self.connects_to(*neighbor))
+                .map(|(dir, _)| dir)
+        };
+        match self {
+            BlockModel::Empty | BlockModel::Cross => Vec::new(),
+            BlockModel::Cube => vec![ModelBox::FULL],
+            BlockModel::Slab => vec![ModelBox::new(Vec3::ZERO, Vec3::new(1.0, 0.5, 1.0))],
+            BlockModel::Stairs => {
+                let facing = HORIZONTAL
+                    .into_iter()
+                    .zip(neighbors)
+                    .find(|(_, neighbor)| neighbor.is_opaque_cube())
+                    .map_or(HORIZONTAL[0], |(dir, _)| dir);
+                let mut step = ModelBox::new(Vec3::new(0.0, 0.5, 0.0), Vec3::ONE);
+                for axis in [0, 2] {
+                    match facing[axis] {
+                        1 => step.min[axis] = 0.5,
+                        -1 => step.max[axis] = 0.5,
+                        _ => {}
+                    }
+                }
+                vec![ModelBox::new(Vec3::ZERO, Vec3::new(1.0, 0.5, 1.0)), step]
+            }
+            BlockModel::Fence => {
+                let mut boxes = vec![post(FENCE_POST, 0.0, 1.0)];
+                for dir in connected() {
+                    for (bottom, top) in FENCE_RAILS {
+                        boxes.push(arm(dir, FENCE_RAIL, bottom, top));
+                    }
+                }
+                boxes
+            }
+            BlockModel::Pane => {
+                // One sheet along each axis it joins something on, so no two faces
+                // of the glass lie on top of each other
+                let mut dirs: Vec<IVec3> = connected().collect();
+                if dirs.is_empty() {
+                    dirs = vec![IVec3::X, IVec3::NEG_X];
+                }
+                [[IVec3::X, IVec3::NEG_X], [IVec3::Z, IVec3::NEG_Z]]
+                    .into_iter()
+                    .filter(|sides| sides.iter().any(|side| dirs.contains(side)))
+                    .map(|sides| {
+                        sides
+                            .into_iter()
+                            .filter(|side| dirs.contains(side))
+                            .fold(post(PANE_HALF_THICKNESS, 0.0, 1.0), reach)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The boxes players and items collide with, given the blocks beside it in
+    /// `HORIZONTAL` order.
+    pub fn collision_boxes(self, neighbors: [BlockType; 4]) -> Vec<ModelBox> {
+        match self {
+            BlockModel::Fence => {
+                let mut boxes = vec![post(FENCE_POST, 0.0, FENCE_COLLISION_HEIGHT)];
+                boxes.extend(
+                    HORIZONTAL
+                        .into_iter()
+                        .zip(neighbors)
+                        .filter(|(_, neighbor)| self.connects_to(*neighbor))
+                        .map(|(dir, _)| arm(dir, FENCE_POST, 0.0, FENCE_COLLISION_HEIGHT)),
+                );
+                boxes
+            }
+            _ => self.boxes(neighbors),
+        }
+    }
+
+    /// Whether the model fills the whole side of its cell towards `side`, a unit
+    /// axis vector.
+    pub fn covers(self, side: IVec3) -> bool {
+        match self {
+            BlockModel::Cube => true,
+            BlockModel::Slab | BlockModel::Stairs => side == IVec3::NEG_Y,
+            _ => false,
+        }
+    }
+}
+
+/// The blocks beside `pos` in `HORIZONTAL` order.
+pub fn horizontal_neighbors(pos: IVec3, chunk_map: &ChunkMap) -> [BlockType; 4] {
+    HORIZONTAL.map(|dir| {
+        let neighbor = pos + dir;
+        chunk_map.get_block(neighbor.x, neighbor.y, neighbor.z)
+    })
+}
+
+/// The collision boxes of the block at `pos`, in world coordinates. Fluids are
+/// drawn as cubes but have none.
+pub fn block_collision_boxes(pos: IVec3, chunk_map: &ChunkMap) -> Vec<ModelBox> {
+    let block = chunk_map.get_block(pos.x, pos.y, pos.z);
+    match block.model() {
+        BlockModel::Empty | BlockModel::Cross => Vec::new(),
+        _ if block.is_fluid() => Vec::new(),
+        model => model
+            .collision_boxes(horizontal_neighbors(pos, chunk_map))
+            .into_iter()
+            .map(|model_box| model_box.translate(pos.as_vec3()))
+            .collect(),
+    }
+}
+
+/// Every block collision box in the world that overlaps `area`.
+pub fn collision_boxes(area: ModelBox, chunk_map: &ChunkMap) -> Vec<ModelBox> {
+    let min = area.min.floor().as_ivec3();
+    let max = area.max.floor().as_ivec3();
+    let mut boxes = Vec::new();
+    // Fences reach up into the cell above their own
+    for y in min.y - 1..=max.y {
+        for z in min.z..=max.z {
+            for x in min.x..=max.x {
+                boxes.extend(
+                    block_collision_boxes(IVec3::new(x, y, z), chunk_map)
+                        .into_iter()
+                        .filter(|block_box| block_box.intersects(&area)),
+                );
+            }
+        }
+    }
+    boxes
+}
+
+/// Whether any fluid block overlaps `area`.
+pub fn touches_fluid(area: ModelBox, chunk_map: &ChunkMap) -> bool {
+    let min = area.min.floor().as_ivec3();
+    let max = area.max.floor().as_ivec3();
+    (min.y..=max.y).any(|y| {
+        (min.z..=max.z).any(|z| (min.x..=max.x).any(|x| chunk_map.get_block(x, y, z).is_fluid()))
+    })
+}
+
+/// The collision box `point` lies in, if any.
+pub fn solid_box_at(point: Vec3, chunk_map: &ChunkMap) -> Option<ModelBox> {
+    let cell = point.floor().as_ivec3();
+    [cell, cell - IVec3::Y]
+        .into_iter()
+        .flat_map(|pos| block_collision_boxes(pos, chunk_map))
+        .find(|block_box| block_box.contains(point))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::chunk::{Chunk, ChunkPos};
+
+    fn world(blocks: &[(IVec3, BlockType)]) -> ChunkMap {
+        let mut chunk_map = ChunkMap::default();
+        chunk_map.chunks.insert(ChunkPos(0, 0), Chunk::new());
+        for (pos, block) in blocks {
+            chunk_map.set_block(pos.x, pos.y, pos.z, *block);
+        }
+        chunk_map
+    }
+
+    #[test]
+    fn stairs_rise_towards_a_neighboring_block() {
+        let air = [BlockType::Air; 4];
+        let step = |neighbors| BlockModel::Stairs.boxes(neighbors)[1];
+        // North by default
+        assert_eq!(step(air).min, Vec3::new(0.0, 0.5, 0.5));
+
+        let mut west = air;
+        west[3] = BlockType::Stone;
+        assert_eq!(
+            step(west),
+            ModelBox::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.5, 1.0, 1.0))
+        );
+
+        // Only full blocks turn them
+        west[3] = BlockType::Water;
+        assert_eq!(step(west), step(air));
+    }
+
+    #[test]
+    fn fences_and_panes_join_their_neighbors() {
+        let chunk_map = world(&[
+            (IVec3::new(5, 10, 5), BlockType::Fence),
+            (IVec3::new(5, 10, 6), BlockType::Fence),
+            (IVec3::new(6, 10, 5), BlockType::Stone),
+            (IVec3::new(4, 10, 5), BlockType::Flower),
+        ]);
+        let pos = IVec3::new(5, 10, 5);
+        let neighbors = horizontal_neighbors(pos, &chunk_map);
+        // A post and two rails each to the north and the east
+        assert_eq!(BlockModel::Fence.boxes(neighbors).len(), 5);
+
+        // Collision is taller than the fence and covers the joins
+        let collision = block_collision_boxes(pos, &chunk_map);
+        assert_eq!(collision.len(), 3);
+        assert!(collision.iter().all(|b| b.max.y == 11.5));
+        assert!(solid_box_at(Vec3::new(5.9, 11.2, 5.5), &chunk_map).is_some());
+        assert!(solid_box_at(Vec3::new(5.1, 11.2, 5.5), &chunk_map).is_none());
+
+        // A lone pane spans its cell east to west
+        let pane = BlockModel::Pane.boxes([BlockType::Air; 4]);
+        assert_eq!(pane.len(), 1);
+        assert_eq!((pane[0].min.x, pane[0].max.x), (0.0, 1.0));
+        assert!(pane[0].min.z > 0.4 && pane[0].max.z < 0.6);
+    }
+
+    #[test]
+    fn plants_and_water_have_no_collision() {
+        let chunk_map = world(&[
+            (IVec3::new(2, 3, 2), BlockType::TallGrass),
+            (IVec3::new(3, 3, 2), BlockType::StoneSlab),
+            (IVec3::new(2, 4, 2), BlockType::Water),
+        ]);
+        let area = ModelBox::new(Vec3::new(2.0, 3.0, 2.0), Vec3::new(4.0, 5.0, 3.0));
+        let boxes = collision_boxes(area, &chunk_map);
+        assert_eq!(
+            boxes,
+            vec![ModelBox::new(
+                Vec3::new(3.0, 3.0, 2.0),
+                Vec3::new(4.0, 3.5, 3.0)
+            )]
+        );
+        assert!(solid_box_at(Vec3::new(2.5, 3.2, 2.5), &chunk_map).is_none());
+        assert!(solid_box_at(Vec3::new(3.5, 3.7, 2.5), &chunk_map).is_none());
+        assert!(solid_box_at(Vec3::new(2.5, 4.5, 2.5), &chunk_map).is_none());
+
+        assert!(touches_fluid(area, &chunk_map));
+        let below = ModelBox::new(Vec3::new(2.0, 3.0, 2.0), Vec3::new(4.0, 3.9, 3.0));
+        assert!(!touches_fluid(below, &chunk_map));
+    }
+}
@@ -0,0 +1,297 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use super::block::BlockType;
+use super::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, Chunk, ChunkMap, ChunkPos};
+use super::generation::WorldGen;
+use super::structure::PlacedStructure;
+
+/// Generation stages in the order a chunk goes through them. A chunk only advances
+/// to a stage once all eight neighbors have reached the previous one, so each stage
+/// can read its neighborhood and write into adjacent chunks without a later stage
+/// of that neighbor overwriting the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GenerationStage {
+    Terrain,
+    Carving,
+    Surface,
+    Features,
+    Lighting,
+}
+
+impl GenerationStage {
+    pub fn previous(self) -> Option<GenerationStage> {
+        match self {
+            GenerationStage::Terrain => None,
+            GenerationStage::Carving => Some(GenerationStage::Terrain),
+            GenerationStage::Surface => Some(GenerationStage::Carving),
+            GenerationStage::Features => Some(GenerationStage::Surface),
+            GenerationStage::Lighting => Some(GenerationStage::Features),
+        }
+    }
+}
+
+/// A chunk that is still being generated, with the last stage it completed.
+pub struct ProtoChunk {
+    pub chunk: Chunk,
+    pub stage: GenerationStage,
+}
+
+/// The chunk a stage is running on plus its eight neighbors, addressed in world
+/// coordinates. Neighbors that already finished generating are read from and
+/// written to the `ChunkMap`. Blocks outside the neighborhood read as air and
+/// writes to them are dropped.
+pub struct GenRegion<'a> {
+    center: ChunkPos,
+    chunks: &'a mut HashMap<ChunkPos, ProtoChunk>,
+    finished: &'a mut ChunkMap,
+    structures: &'a mut Vec<PlacedStructure>,
+}
+
+impl GenRegion<'_> {
+    pub fn center(&self) -> ChunkPos {
+        self.center
+    }
+
+    /// The chunk being generated.
+    pub fn chunk_mut(&mut self) -> &mut Chunk {
+        &mut self
+            .chunks
+            .get_mut(&self.center)
+            .expect("generated chunk is in the pipeline")
+            .chunk
+    }
+
+    /// Records a structure placed by this stage so it can be looked up later.
+    pub fn add_structure(&mut self, structure: PlacedStructure) {
+        self.structures.push(structure);
+    }
+
+    pub fn get_block(&self, wx: i32, wy: i32, wz: i32) -> BlockType {
+        let Some((pos, lx, ly, lz)) = self.locate(wx, wy, wz) else {
+            return BlockType::Air;
+        };
+        match self.chunks.get(&pos) {
+            Some(proto) => proto.chunk.get_block(lx, ly, lz),
+            None => self
+                .finished
+                .chunks
+                .get(&pos)
+                .map(|chunk| chunk.get_block(lx, ly, lz))
+                .unwrap_or(BlockType::Air),
+        }
+    }
+
+    pub fn set_block(&mut self, wx: i32, wy: i32, wz: i32, block: BlockType) {
+        let Some((pos, lx, ly, lz)) = self.locate(wx, wy, wz) else {
+            return;
+        };
+        if let Some(proto) = self.chunks.get_mut(&pos) {
+            proto.chunk.set_block(lx, ly, lz, block);
+        } else if let Some(chunk) = self.finished.chunks.get_mut(&pos) {
+            // Marks the finished chunk dirty so it is meshed again
+            chunk.set_block(lx, ly, lz, block);
+        }
+    }
+
+    fn locate(&self, wx: i32, wy: i32, wz: i32) -> Option<(ChunkPos, usize, usize, usize)> {
+        if wy < 0 || wy >= CHUNK_HEIGHT as i32 {
+            return None;
+        }
+        let cx = wx.div_euclid(CHUNK_SIZE as i32);
+        let cz = wz.div_euclid(CHUNK_SIZE as i32);
+        if (cx - self.center.0).abs() > 1 || (cz - self.center.1).abs() > 1 {
+            return None;
+        }
+        let lx = wx.rem_euclid(CHUNK_SIZE as i32) as usize;
+        let lz = wz.rem_euclid(CHUNK_SIZE as i32) as usize;
+        Some((ChunkPos(cx, cz), lx, wy as usize, lz))
+    }
+}
+
+/// Chunks in the middle of generation. Finished chunks are moved into the
+/// `ChunkMap`; the partially generated margin around them stays here so later
+/// requests (e.g. streaming in new chunks) continue where generation stopped.
+#[derive(Resource, Default)]
+pub struct GenerationPipeline {
+    pub proto_chunks: HashMap<ChunkPos, ProtoChunk>,
+    /// Structures placed since they were last moved into the `StructureIndex`.
+    pub structures: Vec<PlacedStructure>,
+}
+
+impl GenerationPipeline {
+    /// The last stage `pos` completed, or `None` if it hasn't been started.
+    /// Chunks already in the `ChunkMap` are complete.
+    pub fn stage(&self, pos: ChunkPos, chunk_map: &ChunkMap) -> Option<GenerationStage> {
+        if chunk_map.chunks.contains_key(&pos) {
+            return Some(GenerationStage::Lighting);
+        }
+        self.proto_chunks.get(&pos).map(|p| p.stage)
+    }
+
+    /// Runs every stage on `pos` (and as much of its surroundings as that requires)
+    /// and moves the finished chunk into `chunk_map`.
+    pub fn generate(&mut self, pos: ChunkPos, world_gen: &WorldGen, chunk_map: &mut ChunkMap) {
+        self.advance(pos, GenerationStage::Lighting, world_gen, chunk_map);
+    }
+
+    fn advance(
+        &mut self,
+        pos: ChunkPos,
+        target: GenerationStage,
+        world_gen: &WorldGen,
+        chunk_map: &mut ChunkMap,
+    ) {
+        if self.stage(pos, chunk_map) >= Some(target) {
+            return;
+        }
+
+        if let Some(prerequisite) = target.previous() {
+            self.advance(pos, prerequisite, world_gen, chunk_map);
+            for dx in -1..=1 {
+                for dz in -1..=1 {
+                    if dx != 0 || dz != 0 {
+                        let neighbor = ChunkPos(pos.0 + dx, pos.1 + dz);
+                        self.advance(neighbor, prerequisite, world_gen, chunk_map);
+                    }
+                }
+            }
+        }
+
+        self.run_stage(pos, target, world_gen, chunk_map);
+    }
+
+    fn run_stage(
+        &mut self,
+        pos: ChunkPos,
+        stage: GenerationStage,
+        world_gen: &WorldGen,
+        chunk_map: &mut ChunkMap,
+    ) {
+        let generator = &world_gen.generator;
+        let seed = world_gen.seed;
+
+        if stage == GenerationStage::Terrain {
+            let chunk = generator.generate_chunk(pos, seed);
+            self.proto_chunks.insert(
+                pos,
+                ProtoChunk {
+                    chunk,
+                    stage: GenerationStage::Terrain,
+                },
+            );
+            return;
+        }
+
+        let mut region = GenRegion {
+            center: pos,
+            chunks: &mut self.proto_chunks,
+            finished: chunk_map,
+            structures: &mut self.structures,
+        };
+        match stage {
+            GenerationStage::Terrain => unreachable!(),
+            GenerationStage::Carving => generator.carve(&mut region, seed),
+            GenerationStage::Surface => generator.surface(&mut region, seed),
+            GenerationStage::Features => generator.decorate(&mut region, seed),
+            GenerationStage::Lighting => generator.light(&mut region, seed),
+        }
+
+        if stage == GenerationStage::Lighting {
+            if let Some(mut proto) = self.proto_chunks.remove(&pos) {
+                proto.chunk.dirty = false;
+                chunk_map.chunks.insert(pos, proto.chunk);
+            }
+        } else if let Some(proto) = self.proto_chunks.get_mut(&pos) {
+            proto.stage = stage;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::generation::WorldGenerator;
+
+    /// Fills the bottom layer with stone and, on the features stage, places a
+    /// sand block just across the +X border of every chunk. On the lighting stage it
+    /// puts dirt at the -X border of every chunk whose neighbor there has stone.
+    struct BorderMarker;
+
+    impl WorldGenerator for BorderMarker {
+        fn generate_chunk(&self, _pos: ChunkPos, _seed: u32) -> Chunk {
+            let mut chunk = Chunk::new();
+            for lx in 0..CHUNK_SIZE {
+                for lz in 0..CHUNK_SIZE {
+                    chunk.set_block(lx, 0, lz, BlockType::Stone);
+                }
+            }
+            chunk
+        }
+
+        fn decorate(&self, region: &mut GenRegion, _seed: u32) {
+            let pos = region.center();
+            let wx = (pos.0 + 1) * CHUNK_SIZE as i32;
+            let wz = pos.1 * CHUNK_SIZE as i32;
+            region.set_block(wx, 1, wz, BlockType::Sand);
+        }
+
+        fn light(&self, region: &mut GenRegion, _seed: u32) {
+            let pos = region.center();
+            let wx = pos.0 * CHUNK_SIZE as i32;
+            let wz = pos.1 * CHUNK_SIZE as i32;
+            if region.get_block(wx - 1, 0, wz) == BlockType::Stone {
+                region.set_block(wx, 2, wz, BlockType::Dirt);
+            }
+        }
+    }
+
+    #[test]
+    fn neighbors_reach_prerequisite_stage() {
+        let world_gen = WorldGen::new(BorderMarker, 0);
+        let mut pipeline = GenerationPipeline::default();
+        let mut chunk_map = ChunkMap::default();
+
+        pipeline.generate(ChunkPos(0, 0), &world_gen, &mut chunk_map);
+
+        assert_eq!(chunk_map.chunks.len(), 1);
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let stage = pipeline.stage(ChunkPos(dx, dz), &chunk_map);
+                assert!(stage >= Some(GenerationStage::Features));
+            }
+        }
+        assert_eq!(
+            pipeline.stage(ChunkPos(2, 0), &chunk_map),
+            Some(GenerationStage::Surface)
+        );
+    }
+
+    #[test]
+    fn features_written_by_neighbors_survive() {
+        let world_gen = WorldGen::new(BorderMarker, 0);
+        let mut pipeline = GenerationPipeline::default();
+        let mut chunk_map = ChunkMap::default();
+
+        pipeline.generate(ChunkPos(0, 0), &world_gen, &mut chunk_map);
+        pipeline.generate(ChunkPos(1, 0), &world_gen, &mut chunk_map);
+
+        // Written by (-1, 0) into (0, 0) and by (0, 0) into (1, 0).
+        assert_eq!(chunk_map.get_block(0, 1, 0), BlockType::Sand);
+        assert_eq!(chunk_map.get_block(16, 1, 0), BlockType::Sand);
+        assert!(!chunk_map.chunks[&ChunkPos(1, 0)].dirty);
+    }
+
+    #[test]
+    fn later_stages_see_finished_neighbors() {
+        let world_gen = WorldGen::new(BorderMarker, 0);
+        let mut pipeline = GenerationPipeline::default();
+        let mut chunk_map = ChunkMap::default();
+
+        pipeline.generate(ChunkPos(0, 0), &world_gen, &mut chunk_map);
+        pipeline.generate(ChunkPos(1, 0), &world_gen, &mut chunk_map);
+
+        // (1, 0) is lit after (0, 0) already moved into the chunk map
+        assert_eq!(chunk_map.get_block(16, 2, 0), BlockType::Dirt);
+    }
+}
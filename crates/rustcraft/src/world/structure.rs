@@ -0,0 +1,506 @@
+use bevy::prelude::*;
+
+use super::block::BlockType;
+use super::chunk::{CHUNK_HEIGHT, CHUNK_SIZE, Chunk, ChunkPos};
+use super::generation::{WorldGenerator, column_hash};
+use super::model::BlockModel;
+use super::pipeline::GenRegion;
+
+/// Structures are placed on a grid of cells this many chunks wide, at most one per cell.
+const STRUCTURE_SPACING: i32 = 6;
+/// Out of 8, how many cells get a structure.
+const STRUCTURE_CHANCE: u32 = 5;
+const STRUCTURE_SALT: u32 = 0x5f37_59df;
+/// Pieces on ground steeper than this (max minus min height) are skipped.
+const MAX_SLOPE: i32 = 4;
+const DUNGEON_DEPTH: i32 = 10;
+const FOUNDATION_BLOCK: BlockType = BlockType::Stone;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StructureKind {
+    Village,
+    Ruin,
+    Dungeon,
+}
+
+/// A block grid placed as one piece of a structure. `None` cells keep whatever
+/// block is already in the world.
+pub struct StructureTemplate {
+    pub size: IVec3,
+    pub blocks: Vec<Option<BlockType>>,
+}
+
+impl StructureTemplate {
+    /// Builds a template from horizontal layers listed bottom to top. Each layer is a
+    /// list of rows along +Z, each row a string of cells along +X:
+    /// `#` stone, `P` wood, `L` leaves, `D` dirt, `~` water, `.` air, ` ` keep.
+    pub fn from_layers(layers: &[&[&str]]) -> Self {
+        let size_y = layers.len() as i32;
+        let size_z = layers.iter().map(|l| l.len()).max().unwrap_or(0) as i32;
+        let size_x = layers
+            .iter()
+            .flat_map(|l| l.iter().map(|row| row.len()))
+            .max()
+            .unwrap_or(0) as i32;
+        let size = IVec3::new(size_x, size_y, size_z);
+
+        let mut blocks = vec![None; (size_x * size_y * size_z) as usize];
+        for (y, layer) in layers.iter().enumerate() {
+            for (z, row) in layer.iter().enumerate() {
+                for (x, cell) in row.chars().enumerate() {
+                    let block = match cell {
+                        '#' => Some(BlockType::Stone),
+                        'P' => Some(BlockType::Wood),
+                        'L' => Some(BlockType::Leaves),
+                        'D' => Some(BlockType::Dirt),
+                        '~' => Some(BlockType::Water),
+                        '.' => Some(BlockType::Air),
+                        _ => None,
+                    };
+                    let index = x as i32 + z as i32 * size_x + y as i32 * size_x * size_z;
+                    blocks[index as usize] = block;
+                }
+            }
+        }
+
+        Self { size, blocks }
+    }
+
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Option<BlockType> {
+        let index = x + z * self.size.x + y * self.size.x * self.size.z;
+        self.blocks[index as usize]
+    }
+}
+
+/// One placed piece of a structure, with its inclusive world-space bounds. These
+/// include any foundation filled in under it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructurePiece {
+    pub min: IVec3,
+    pub max: IVec3,
+}
+
+impl StructurePiece {
+    pub fn contains(&self, pos: IVec3) -> bool {
+        pos.cmpge(self.min).all() && pos.cmple(self.max).all()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacedStructure {
+    pub kind: StructureKind,
+    pub pieces: Vec<StructurePiece>,
+}
+
+impl PlacedStructure {
+    pub fn contains(&self, pos: IVec3) -> bool {
+        self.pieces.iter().any(|piece| piece.contains(pos))
+    }
+}
+
+/// Every structure generated so far, for looking up what is at a position.
+#[derive(Resource, Default)]
+pub struct StructureIndex {
+    pub structures: Vec<PlacedStructure>,
+}
+
+impl StructureIndex {
+    pub fn structure_at(&self, pos: IVec3) -> Option<&PlacedStructure> {
+        self.structures.iter().find(|s| s.contains(pos))
+    }
+}
+
+/// Wraps another generator and places villages, ruins and dungeons on its terrain
+/// during the features stage.
+pub struct WithStructures<G>(pub G);
+
+impl<G: WorldGenerator> WorldGenerator for WithStructures<G> {
+    fn generate_chunk(&self, pos: ChunkPos, seed: u32) -> Chunk {
+        self.0.generate_chunk(pos, seed)
+    }
+
+    fn carve(&self, region: &mut GenRegion, seed: u32) {
+        self.0.carve(region, seed);
+    }
+
+    fn surface(&self, region: &mut GenRegion, seed: u32) {
+        self.0.surface(region, seed);
+    }
+
+    fn decorate(&self, region: &mut GenRegion, seed: u32) {
+        self.0.decorate(region, seed);
+
+        if let Some(kind) = structure_start(region.center(), seed) {
+            place_structure(region, kind, seed);
+        }
+    }
+
+    fn light(&self, region: &mut GenRegion, seed: u32) {
+        self.0.light(region, seed);
+    }
+}
+
+/// The structure whose start chunk is `pos`, if any. Each grid cell picks one chunk
+/// away from its edges so the structure fits in the chunk's neighborhood.
+fn structure_start(pos: ChunkPos, seed: u32) -> Option<StructureKind> {
+    let cell_x = pos.0.div_euclid(STRUCTURE_SPACING);
+    let cell_z = pos.1.div_euclid(STRUCTURE_SPACING);
+    let hash = column_hash(cell_x, cell_z, seed ^ STRUCTURE_SALT);
+
+    if hash % 8 >= STRUCTURE_CHANCE {
+        return None;
+    }
+
+    let inner = (STRUCTURE_SPACING - 2) as u32;
+    let start_x = cell_x * STRUCTURE_SPACING + 1 + ((hash >> 8) % inner) as i32;
+    let start_z = cell_z * STRUCTURE_SPACING + 1 + ((hash >> 16) % inner) as i32;
+    if pos != ChunkPos(start_x, start_z) {
+        return None;
+    }
+
+    Some(match (hash >> 24) % 3 {
+        0 => StructureKind::Village,
+        1 => StructureKind::Ruin,
+        _ => StructureKind::Dungeon,
+    })
+}
+
+fn place_structure(region: &mut GenRegion, kind: StructureKind, seed: u32) {
+    let pos = region.center();
+    let center = IVec2::new(
+        pos.0 * CHUNK_SIZE as i32 + CHUNK_SIZE as i32 / 2,
+        pos.1 * CHUNK_SIZE as i32 + CHUNK_SIZE as i32 / 2,
+    );
+
+    let mut pieces = Vec::new();
+    match kind {
+        StructureKind::Village => {
+            pieces.extend(place_on_surface(region, &well(), center));
+            let hash = column_hash(center.x, center.y, seed);
+            for (i, offset) in [(-9, -9), (9, -9), (-9, 9), (9, 9)].into_iter().enumerate() {
+                // Skip some houses so villages don't all look alike
+                if (hash >> (i * 3)).is_multiple_of(4) {
+                    continue;
+                }
+                let house_center = center + IVec2::new(offset.0, offset.1);
+                pieces.extend(place_on_surface(region, &house(), house_center));
+            }
+        }
+        StructureKind::Ruin => {
+            pieces.extend(place_on_surface(region, &ruin(), center));
+        }
+        StructureKind::Dungeon => {
+            let template = dungeon();
+            if let Some(ground) = ground_height(region, center.x, center.y) {
+                let base = (ground - DUNGEON_DEPTH).max(1);
+                pieces.push(place_template(region, &template, center, base));
+            }
+        }
+    }
+
+    if !pieces.is_empty() {
+        region.add_structure(PlacedStructure { kind, pieces });
+    }
+}
+
+/// Places `template` centered on `center`, resting on the terrain: the base height is
+/// the average ground height under the footprint and the columns below are filled
+/// down to the ground. Returns `None` on water or steep ground.
+fn place_on_surface(
+    region: &mut GenRegion,
+    template: &StructureTemplate,
+    center: IVec2,
+) -> Option<StructurePiece> {
+    let min = center - IVec2::new(template.size.x, template.size.z) / 2;
+
+    let mut heights = Vec::new();
+    for x in 0..template.size.x {
+        for z in 0..template.size.z {
+            let (wx, wz) = (min.x + x, min.y + z);
+            let ground = ground_height(region, wx, wz)?;
+            if region.get_block(wx, ground, wz).is_fluid() {
+                return None;
+            }
+            heights.push(ground);
+        }
+    }
+
+    let lowest = *heights.iter().min()?;
+    let highest = *heights.iter().max()?;
+    if highest - lowest > MAX_SLOPE {
+        return None;
+    }
+    let base = heights.iter().sum::<i32>() / heights.len() as i32 + 1;
+    if base + template.size.y >= CHUNK_HEIGHT as i32 {
+        return None;
+    }
+
+    // Foundations: fill from just under the piece down to solid ground
+    for x in 0..template.size.x {
+        for z in 0..template.size.z {
+            let (wx, wz) = (min.x + x, min.y + z);
+            let mut y = base - 1;
+            while y >= 0 && !is_ground(region.get_block(wx, y, wz)) {
+                region.set_block(wx, y, wz, FOUNDATION_BLOCK);
+                y -= 1;
+            }
+        }
+    }
+
+    let mut piece = place_template(region, template, center, base);
+    piece.min.y = lowest + 1;
+    Some(piece)
+}
+
+fn place_template(
+    region: &mut GenRegion,
+    template: &StructureTemplate,
+    center: IVec2,
+    base: i32,
+) -> StructurePiece {
+    let min = IVec3::new(
+        center.x - template.size.x / 2,
+        base,
+        center.y - template.size.z / 2,
+    );
+
+    for y in 0..template.size.y {
+        for z in 0..template.size.z {
+            for x in 0..template.size.x {
+                if let Some(block) = template.get(x, y, z) {
+                    region.set_block(min.x + x, min.y + y, min.z + z, block);
+                }
+            }
+        }
+    }
+
+    StructurePiece {
+        min,
+        max: min + template.size - IVec3::ONE,
+    }
+}
+
+/// Blocks a structure can stand on (or, for fluids, is kept off of); trees, plants
+/// and air are built over or through.
+fn is_ground(block: BlockType) -> bool {
+    (block.is_solid() || block.is_fluid())
+        && block.model() == BlockModel::Cube
+        && !matches!(block, BlockType::Wood | BlockType::Leaves)
+}
+
+/// The y of the highest ground (or fluid) block in a column of the region.
+fn ground_height(region: &GenRegion, wx: i32, wz: i32) -> Option<i32> {
+    (0..CHUNK_HEIGHT as i32)
+        .rev()
+        .find(|&y| is_ground(region.get_block(wx, y, wz)))
+}
+
+// --- Templates ---
+
+fn well() -> StructureTemplate {
+    StructureTemplate::from_layers(&[
+        &["###", "#~#", "###"],
+        &["#.#", "...", "#.#"],
+        &["P.P", "...", "P.P"],
+        &["PPP", "PPP", "PPP"],
+    ])
+}
+
+fn house() -> StructureTemplate {
+    StructureTemplate::from_layers(&[
+        &["#####", "#####", "#####", "#####", "#####"],
+        &["PP.PP", "P...P", "P...P", "P...P", "PPPPP"],
+        &["PP.PP", "P...P", "....P", "P...P", "PPPPP"],
+        &["PPPPP", "P...P", "P...P", "P...P", "PPPPP"],
+        &["PPPPP", "PPPPP", "PPPPP", "PPPPP", "PPPPP"],
+    ])
+}
+
+fn ruin() -> StructureTemplate {
+    StructureTemplate::from_layers(&[
+        &[
+            "#######", "#.....#", "#.....#", "#.....#", "#.....#", "#.....#", "#######",
+        ],
+        &[
+            "##  ###", "#.....#", "      #", "#.....#", "#......", "#......", "####  #",
+        ],
+        &[
+            "#    ##", "      #", "       ", "#      ", "#      ", "       ", "##    #",
+        ],
+        &[
+            "#      ", "       ", "       ", "       ", "       ", "       ", "      #",
+        ],
+    ])
+}
+
+fn dungeon() -> StructureTemplate {
+    let wall = "#######";
+    let room = "#.....#";
+    let floor: &[&str] = &[wall, wall, wall, wall, wall, wall, wall];
+    let middle: &[&str] = &[wall, room, room, room, room, room, wall];
+    let chest: &[&str] = &[wall, room, room, "#..P..#", room, room, wall];
+    StructureTemplate::from_layers(&[floor, chest, middle, middle, floor])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::chunk::ChunkMap;
+    use crate::world::generation::{WorldGen, WorldGenerator};
+    use crate::world::pipeline::GenerationPipeline;
+
+    /// Stone up to y = 10 with a pool of water on top of the middle of chunk
+    /// (0, 0), where the features stage tries to build a house.
+    struct PoolSite;
+
+    impl WorldGenerator for PoolSite {
+        fn generate_chunk(&self, pos: ChunkPos, _seed: u32) -> Chunk {
+            let mut chunk = Chunk::new();
+            for lx in 0..CHUNK_SIZE {
+                for lz in 0..CHUNK_SIZE {
+                    for y in 0..=10 {
+                        chunk.set_block(lx, y, lz, BlockType::Stone);
+                    }
+                    if pos == ChunkPos(0, 0) && (6..10).contains(&lx) && (6..10).contains(&lz) {
+                        chunk.set_block(lx, 10, lz, BlockType::Water);
+                    }
+                }
+            }
+            chunk
+        }
+
+        fn decorate(&self, region: &mut GenRegion, _seed: u32) {
+            if region.center() != ChunkPos(0, 0) {
+                return;
+            }
+            if let Some(piece) = place_on_surface(region, &house(), IVec2::new(8, 8)) {
+                region.add_structure(PlacedStructure {
+                    kind: StructureKind::Village,
+                    pieces: vec![piece],
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn template_layers_map_to_blocks() {
+        let template = StructureTemplate::from_layers(&[&["#P", ". "], &["~L", "D#"]]);
+        assert_eq!(template.size, IVec3::new(2, 2, 2));
+        assert_eq!(template.get(0, 0, 0), Some(BlockType::Stone));
+        assert_eq!(template.get(1, 0, 0), Some(BlockType::Wood));
+        assert_eq!(template.get(0, 0, 1), Some(BlockType::Air));
+        assert_eq!(template.get(1, 0, 1), None);
+        assert_eq!(template.get(0, 1, 0), Some(BlockType::Water));
+        assert_eq!(template.get(1, 1, 1), Some(BlockType::Stone));
+    }
+
+    #[test]
+    fn footprints_over_water_are_skipped() {
+        let world_gen = WorldGen::new(PoolSite, 0);
+        let mut pipeline = GenerationPipeline::default();
+        let mut chunk_map = ChunkMap::default();
+        pipeline.generate(ChunkPos(0, 0), &world_gen, &mut chunk_map);
+
+        assert!(pipeline.structures.is_empty());
+        assert_eq!(chunk_map.get_block(8, 10, 8), BlockType::Water);
+        assert_eq!(chunk_map.get_block(8, 11, 8), BlockType::Air);
+    }
+
+    /// Stone up to y = 10, with a two block deep pit in the middle of every chunk.
+    struct PitSite;
+
+    impl WorldGenerator for PitSite {
+        fn generate_chunk(&self, _pos: ChunkPos, _seed: u32) -> Chunk {
+            let mut chunk = Chunk::new();
+            for lx in 0..CHUNK_SIZE {
+                for lz in 0..CHUNK_SIZE {
+                    let top = if lx == CHUNK_SIZE / 2 && lz == CHUNK_SIZE / 2 {
+                        8
+                    } else {
+                        10
+                    };
+                    for y in 0..=top {
+                        chunk.set_block(lx, y, lz, BlockType::Stone);
+                    }
+                }
+            }
+            chunk
+        }
+    }
+
+    #[test]
+    fn pipeline_places_structures_on_foundations() {
+        // A seed whose first grid cell starts a structure built on the surface
+        let (seed, start) = (0..)
+            .find_map(|seed| {
+                (1..STRUCTURE_SPACING - 1)
+                    .flat_map(|x| (1..STRUCTURE_SPACING - 1).map(move |z| ChunkPos(x, z)))
+                    .find(|&pos| {
+                        matches!(
+                            structure_start(pos, seed),
+                            Some(StructureKind::Village | StructureKind::Ruin)
+                        )
+                    })
+                    .map(|pos| (seed, pos))
+            })
+            .unwrap();
+        let world_gen = WorldGen::new(WithStructures(PitSite), seed);
+        let mut pipeline = GenerationPipeline::default();
+        let mut chunk_map = ChunkMap::default();
+        pipeline.generate(start, &world_gen, &mut chunk_map);
+
+        let mut index = StructureIndex::default();
+        index.structures.append(&mut pipeline.structures);
+        assert_eq!(index.structures.len(), 1);
+
+        // The pit under the middle of the structure is filled in and part of it
+        let center = IVec3::new(
+            start.0 * CHUNK_SIZE as i32 + CHUNK_SIZE as i32 / 2,
+            9,
+            start.1 * CHUNK_SIZE as i32 + CHUNK_SIZE as i32 / 2,
+        );
+        assert_eq!(
+            chunk_map.get_block(center.x, center.y, center.z),
+            FOUNDATION_BLOCK
+        );
+        assert!(index.structure_at(center).is_some());
+        assert!(index.structure_at(center - IVec3::Y).is_none());
+    }
+
+    #[test]
+    fn one_start_per_grid_cell() {
+        for cell_x in -3..3 {
+            for cell_z in -3..3 {
+                let starts = (0..STRUCTURE_SPACING)
+                    .flat_map(|dx| (0..STRUCTURE_SPACING).map(move |dz| (dx, dz)))
+                    .filter(|&(dx, dz)| {
+                        let pos = ChunkPos(
+                            cell_x * STRUCTURE_SPACING + dx,
+                            cell_z * STRUCTURE_SPACING + dz,
+                        );
+                        structure_start(pos, 42).is_some()
+                    })
+                    .count();
+                assert!(starts <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn index_finds_structure_by_position() {
+        let index = StructureIndex {
+            structures: vec![PlacedStructure {
+                kind: StructureKind::Ruin,
+                pieces: vec![StructurePiece {
+                    min: IVec3::new(0, 10, 0),
+                    max: IVec3::new(6, 13, 6),
+                }],
+            }],
+        };
+        assert_eq!(
+            index.structure_at(IVec3::new(3, 12, 6)).map(|s| s.kind),
+            Some(StructureKind::Ruin)
+        );
+        assert!(index.structure_at(IVec3::new(3, 14, 6)).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::WorldDir;
+use crate::events::DayNightChangedEvent;
+use crate::player::camera::GameState;
+
+/// Real seconds a full day takes at a `rate` of 1.
+pub const DAY_LENGTH: f64 = 1200.0;
+
+/// Time of day a new world starts at: early morning.
+const START_TIME: f64 = 0.3;
+
+/// Where `WorldTime` is kept inside the `WorldDir`.
+const TIME_FILE: &str = "time.txt";
+
+/// In-game time. The integer part of `time` counts days since the world was created
+/// and the fraction is the time of day: 0.0 is midnight, 0.25 sunrise, 0.5 noon and
+/// 0.75 sunset.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct WorldTime {
+    pub time: f64,
+    /// How fast time passes relative to `DAY_LENGTH`; 0 stops the clock.
+    pub rate: f64,
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self {
+            time: START_TIME,
+            rate: 1.0,
+        }
+    }
+}
+
+impl WorldTime {
+    pub fn day(&self) -> u64 {
+        self.time.floor() as u64
+    }
+
+    pub fn time_of_day(&self) -> f32 {
+        self.time.fract() as f32
+    }
+
+    /// Between sunrise and sunset.
+    pub fn is_day(&self) -> bool {
+        (0.25..0.75).contains(&self.time_of_day())
+    }
+
+    pub fn advance(&mut self, seconds: f64) {
+        self.time += seconds * self.rate / DAY_LENGTH;
+    }
+
+    /// Reads the time saved in `dir`, keeping the current rate. A world without a
+    /// saved time keeps the current time as well.
+    pub fn load(&mut self, dir: &Path) -> io::Result<()> {
+        let text = match fs::read_to_string(dir.join(TIME_FILE)) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        self.time = text.trim().parse().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?} is not a time", text.trim()),
+            )
+        })?;
+        Ok(())
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(TIME_FILE), format!("{}\n", self.time))
+    }
+}
+
+pub fn load_world_time(world_dir: Option<Res<WorldDir>>, mut world_time: ResMut<WorldTime>) {
+    let Some(world_dir) = world_dir else {
+        return;
+    };
+    if let Err(err) = world_time.load(&world_dir.0) {
+        warn!("Failed to load world time from {:?}: {}", world_dir.0, err);
+    }
+}
+
+pub fn save_world_time(
+    mut exit: EventReader<AppExit>,
+    world_dir: Option<Res<WorldDir>>,
+    world_time: Res<WorldTime>,
+) {
+    if exit.read().last().is_none() {
+        return;
+    }
+    let Some(world_dir) = world_dir else {
+        return;
+    };
+    if let Err(err) = world_time.save(&world_dir.0) {
+        warn!("Failed to save world time to {:?}: {}", world_dir.0, err);
+    }
+}
+
+/// Moves the clock forward while the game isn't paused and reports sunrise and
+/// sunset.
+pub fn advance_world_time(
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    mut world_time: ResMut<WorldTime>,
+    mut day_night: EventWriter<DayNightChangedEvent>,
+) {
+    if *game_state == GameState::Paused {
+        return;
+    }
+
+    let was_day = world_time.is_day();
+    world_time.advance(time.delta_secs_f64());
+    if world_time.is_day() != was_day {
+        day_night.send(DayNightChangedEvent {
+            is_day: world_time.is_day(),
+            day: world_time.day(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_of_day_wraps_into_the_next_day() {
+        let mut world_time = WorldTime {
+            time: 0.7,
+            rate: 2.0,
+        };
+        assert!(world_time.is_day());
+
+        world_time.advance(DAY_LENGTH * 0.05);
+        assert!(!world_time.is_day());
+        assert_eq!(world_time.day(), 0);
+
+        world_time.advance(DAY_LENGTH * 0.2);
+        assert_eq!(world_time.day(), 1);
+        assert!((world_time.time_of_day() - 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn time_survives_a_save() {
+        let dir = std::env::temp_dir().join(format!("rustcraft-time-{}", std::process::id()));
+        let saved = WorldTime {
+            time: 3.625,
+            rate: 1.0,
+        };
+        saved.save(&dir).unwrap();
+
+        let mut loaded = WorldTime {
+            time: 0.0,
+            rate: 4.0,
+        };
+        loaded.load(&dir).unwrap();
+        assert_eq!(loaded.time, 3.625);
+        assert_eq!(loaded.rate, 4.0);
+
+        fs::remove_dir_all(&dir).unwrap();
+        let mut missing = WorldTime::default();
+        missing.load(&dir).unwrap();
+        assert_eq!(missing, WorldTime::default());
+    }
+}
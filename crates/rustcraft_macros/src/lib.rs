@@ -7,12 +7,18 @@ fn event_to_trait_method(event_name: &str) -> Option<&'static str> {
     match event_name {
         "BlockPlaced" => Some("on_block_placed"),
         "BlockRemoved" => Some("on_block_removed"),
+        "BlockDamaged" => Some("on_block_damaged"),
         "PlayerMoved" => Some("on_player_moved"),
         "GameModeChanged" => Some("on_gamemode_changed"),
         "InventoryPickedUp" => Some("on_inventory_picked_up"),
         "InventoryDropped" => Some("on_inventory_dropped"),
         "ItemDroppedToWorld" => Some("on_item_dropped_to_world"),
         "ItemsCollected" => Some("on_items_collected"),
+        "DayNightChanged" => Some("on_day_night_changed"),
+        "SprintToggled" => Some("on_sprint_toggled"),
+        "SneakToggled" => Some("on_sneak_toggled"),
+        "PlayerDamaged" => Some("on_player_damaged"),
+        "PlayerDied" => Some("on_player_died"),
         _ => None,
     }
 }